@@ -0,0 +1,274 @@
+//! Derive macro for clap_conf.
+//!
+//! Use it through `clap_conf::ClapConf`, see the docs there.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Lit, Meta, NestedMeta, Type};
+
+#[derive(Default)]
+struct FieldConf {
+    arg: Option<String>,
+    short: Option<String>,
+    long: Option<String>,
+    conf: Option<String>,
+    env: Option<String>,
+    default: Option<String>,
+    help: Option<String>,
+}
+
+enum Kind {
+    Plain,
+    Bool,
+    Opt(Type),
+    List(Type),
+}
+
+/// If the type is "Name<Inner>" return Inner
+fn inner_type(ty: &Type, name: &str) -> Option<Type> {
+    if let Type::Path(p) = ty {
+        let seg = p.path.segments.last()?;
+        if seg.ident != name {
+            return None;
+        }
+        if let syn::PathArguments::AngleBracketed(a) = &seg.arguments {
+            if let Some(syn::GenericArgument::Type(t)) = a.args.first() {
+                return Some(t.clone());
+            }
+        }
+    }
+    None
+}
+
+fn kind(ty: &Type) -> Kind {
+    if let Some(t) = inner_type(ty, "Option") {
+        return Kind::Opt(t);
+    }
+    if let Some(t) = inner_type(ty, "Vec") {
+        return Kind::List(t);
+    }
+    if let Type::Path(p) = ty {
+        if p.path.is_ident("bool") {
+            return Kind::Bool;
+        }
+    }
+    Kind::Plain
+}
+
+fn lit_string(l: &Lit) -> syn::Result<String> {
+    match l {
+        Lit::Str(s) => Ok(s.value()),
+        Lit::Char(c) => Ok(c.value().to_string()),
+        Lit::Int(i) => Ok(i.to_string()),
+        Lit::Float(f) => Ok(f.to_string()),
+        Lit::Bool(b) => Ok(b.value.to_string()),
+        l => Err(syn::Error::new(l.span(), "expected a literal value")),
+    }
+}
+
+fn field_conf(f: &syn::Field) -> syn::Result<FieldConf> {
+    let mut res = FieldConf::default();
+    for a in &f.attrs {
+        if !a.path.is_ident("conf") {
+            continue;
+        }
+        let list = match a.parse_meta()? {
+            Meta::List(l) => l,
+            m => return Err(syn::Error::new(m.span(), "expected #[conf(..)]")),
+        };
+        for n in list.nested {
+            let nv = match n {
+                NestedMeta::Meta(Meta::NameValue(nv)) => nv,
+                n => return Err(syn::Error::new(n.span(), "expected name = value")),
+            };
+            let v = Some(lit_string(&nv.lit)?);
+            let name = nv
+                .path
+                .get_ident()
+                .map(|i| i.to_string())
+                .unwrap_or_default();
+            match name.as_str() {
+                "arg" => res.arg = v,
+                "short" => res.short = v,
+                "long" => res.long = v,
+                "conf" => res.conf = v,
+                "env" => res.env = v,
+                "default" => res.default = v,
+                "help" => res.help = v,
+                _ => {
+                    return Err(syn::Error::new(
+                        nv.path.span(),
+                        "unknown conf option, expected one of: arg, short, long, conf, env, default, help",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Generate `impl clap_conf::ClapConf` for a struct with named fields.
+///
+/// Every field may have `#[conf(arg = "..", short = 'x', long = "..", conf = "a.b", env = "..", default = "..", help = "..")]`.
+/// arg and conf default to the field name. Fields must be `FromStr`.
+/// `bool` fields become flags, which may also be set by "true"/"false", "yes"/"no", "on"/"off" or "1"/"0",
+/// any other value is an error.
+/// `Option` fields may be missing, and `Vec` fields take multiple values.
+#[proc_macro_derive(ClapConf, attributes(conf))]
+pub fn derive_clap_conf(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match derive(input) {
+        Ok(t) => t.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn derive(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(n) => &n.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "ClapConf needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "ClapConf can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut args = Vec::new();
+    let mut loads = Vec::new();
+    let mut idents = Vec::new();
+    for f in fields {
+        let ident = f.ident.clone().expect("named field");
+        let fname = ident.to_string();
+        let fc = field_conf(f)?;
+        let arg = fc.arg.clone().unwrap_or_else(|| fname.clone());
+        let conf = fc.conf.clone().unwrap_or_else(|| fname.clone());
+        let long = fc.long.clone().unwrap_or_else(|| arg.replace('_', "-"));
+        let k = kind(&f.ty);
+
+        //clap arg
+        let mut a = quote! { ::clap_conf::clap::Arg::with_name(#arg).long(#long) };
+        if let Some(s) = &fc.short {
+            a = quote! { #a.short(#s) };
+        }
+        let help = match (&fc.help, &fc.default) {
+            (Some(h), Some(d)) => Some(format!("{} [default: {}]", h, d)),
+            (Some(h), None) => Some(h.clone()),
+            (None, Some(d)) => Some(format!("[default: {}]", d)),
+            (None, None) => None,
+        };
+        if let Some(h) = help {
+            a = quote! { #a.help(#h) };
+        }
+        match k {
+            Kind::Bool => {}
+            Kind::List(_) => a = quote! { #a.takes_value(true).multiple(true) },
+            _ => a = quote! { #a.takes_value(true) },
+        }
+        args.push(a);
+
+        //lookup chain
+        let mut chain = quote! { .arg(#arg).conf(#conf) };
+        if let Some(e) = &fc.env {
+            chain = quote! { #chain.env(#e) };
+        }
+        let parse = quote! { ::clap_conf::__private::parse };
+        let def_or = |missing: TokenStream2| match &fc.default {
+            Some(d) => quote! { #parse(#d, #fname)? },
+            None => missing,
+        };
+        let load = match &k {
+            Kind::Plain => {
+                let def = def_or(quote! { return Err(_e) });
+                quote! {
+                    match g.grab()#chain.req() {
+                        Ok(v) => #parse(&v.to_string(), #fname)?,
+                        Err(_e) => #def,
+                    }
+                }
+            }
+            Kind::Bool => {
+                let def = match &fc.default {
+                    Some(d) => quote! { ::clap_conf::__private::parse_flag(#d, #fname)? },
+                    None => quote! { false },
+                };
+                quote! {
+                    match g.bool_grab()#chain.try_done()? {
+                        Some(b) => b,
+                        None => #def,
+                    }
+                }
+            }
+            Kind::Opt(t) => {
+                let def = match &fc.default {
+                    Some(d) => quote! { Some(#parse::<#t>(#d, #fname)?) },
+                    None => quote! { None },
+                };
+                quote! {
+                    match g.grab()#chain.done() {
+                        Some(v) => Some(#parse::<#t>(&v.to_string(), #fname)?),
+                        None => #def,
+                    }
+                }
+            }
+            Kind::List(t) => {
+                let def = match &fc.default {
+                    Some(d) => quote! {
+                        #d.split(',')
+                            .map(|v| #parse::<#t>(v.trim(), #fname))
+                            .collect::<Result<Vec<#t>, _>>()?
+                    },
+                    None => quote! { Vec::new() },
+                };
+                let filter = quote! { ::clap_conf::Filter };
+                let mut lists = quote! {
+                    g.list(#arg, #filter::Arg).or_else(|| g.list(#conf, #filter::Conf))
+                };
+                if let Some(e) = &fc.env {
+                    lists = quote! { #lists.or_else(|| g.list(#e, #filter::Env)) };
+                }
+                quote! {
+                    match #lists {
+                        Some(v) => v
+                            .iter()
+                            .map(|v| #parse::<#t>(v, #fname))
+                            .collect::<Result<Vec<#t>, _>>()?,
+                        None => #def,
+                    }
+                }
+            }
+        };
+        loads.push(quote! { let #ident = #load; });
+        idents.push(ident);
+    }
+
+    let (impl_gen, ty_gen, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_gen ::clap_conf::ClapConf for #name #ty_gen #where_clause {
+            fn add_args<'a, 'b>(app: ::clap_conf::clap::App<'a, 'b>) -> ::clap_conf::clap::App<'a, 'b> {
+                app #(.arg(#args))*
+            }
+
+            fn from_getter<'g, G: ::clap_conf::Getter<'g>>(
+                g: &'g G,
+            ) -> Result<Self, ::clap_conf::ConfError> {
+                use ::clap_conf::Getter as _;
+                #(#loads)*
+                Ok(#name { #(#idents),* })
+            }
+        }
+    })
+}
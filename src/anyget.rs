@@ -0,0 +1,169 @@
+use crate::convert::Localizer;
+use crate::env::EnvFile;
+use crate::iniget::Ini;
+use crate::load::{first_or_err, load_first, LoadFail, LoadReport};
+use crate::paths::expand_path;
+use crate::replace::ConfError;
+use crate::{Filter, Getter};
+use std::path::{Path, PathBuf};
+
+/// A config file of any supported format, chosen by file extension when loaded.
+#[derive(Debug)]
+pub enum AnyConf {
+    Toml(toml::Value),
+    Yaml(serde_yaml::Value),
+    Json(serde_json::Value),
+    Ini(Ini),
+    Env(EnvFile),
+}
+
+impl AnyConf {
+    /// Parse the contents using the format suggested by the extension.
+    /// Files with no extension are treated as toml.
+    pub fn parse(ext: Option<&str>, s: &str) -> Result<Self, ConfError> {
+        match ext {
+            None | Some("toml") => Ok(AnyConf::Toml(s.parse()?)),
+            Some("yaml") | Some("yml") => Ok(AnyConf::Yaml(serde_yaml::from_str(s)?)),
+            Some("json") => Ok(AnyConf::Json(serde_json::from_str(s)?)),
+            Some("ini") | Some("conf") => Ok(AnyConf::Ini(s.parse()?)),
+            Some("env") => Ok(AnyConf::Env(s.parse()?)),
+            Some(e) => Err(ConfError::Message(format!(
+                "Unknown config file extension '{}'",
+                e
+            ))),
+        }
+    }
+}
+
+fn ext_of(p: &Path) -> Option<&str> {
+    //".env" has no extension as far as Path is concerned
+    match p.file_name().and_then(|n| n.to_str()) {
+        Some(".env") => Some("env"),
+        _ => p.extension().and_then(|e| e.to_str()),
+    }
+}
+
+pub fn load_config<S: AsRef<str>>(s: S) -> Result<Localizer<AnyConf>, ConfError> {
+    let fname = PathBuf::from(expand_path(s.as_ref())?);
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v = AnyConf::parse(ext_of(&fname), &fcont).map_err(|e| e.in_file(&fname))?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// As load_config, but keeps the reason for failing, including the position of parse errors.
+pub fn try_load_config<S: AsRef<str>>(s: S) -> Result<Localizer<AnyConf>, LoadFail> {
+    let fname = PathBuf::from(expand_path(s.as_ref())?);
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v = AnyConf::parse(ext_of(&fname), &fcont)?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// Load the first config file that can be loaded, along with a report of every candidate tried.
+pub fn load_first_config_report<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> (Option<Localizer<AnyConf>>, LoadReport) {
+    load_first(a, i, |s| try_load_config(s))
+}
+
+pub fn load_first_config<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> Result<Localizer<AnyConf>, ConfError> {
+    first_or_err(a, load_first_config_report(a, i))
+}
+
+impl<'a> Getter<'a> for AnyConf {
+    type Out = String;
+    type Iter = std::vec::IntoIter<String>;
+    fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        match self {
+            AnyConf::Toml(v) => v.bool_flag(s, f),
+            AnyConf::Yaml(v) => v.bool_flag(s, f),
+            AnyConf::Json(v) => v.bool_flag(s, f),
+            AnyConf::Ini(v) => v.bool_flag(s, f),
+            AnyConf::Env(v) => v.bool_flag(s, f),
+        }
+    }
+
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        match self {
+            AnyConf::Toml(v) => v.has(s, f),
+            AnyConf::Yaml(v) => v.has(s, f),
+            AnyConf::Json(v) => v.has(s, f),
+            AnyConf::Ini(v) => v.has(s, f),
+            AnyConf::Env(v) => v.has(s, f),
+        }
+    }
+
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
+        match self {
+            AnyConf::Toml(v) => v.value(s, f),
+            AnyConf::Yaml(v) => v.value(s, f),
+            AnyConf::Json(v) => v.value(s, f),
+            AnyConf::Ini(v) => v.value(s, f),
+            AnyConf::Env(v) => v.value(s, f),
+        }
+    }
+
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Self::Iter> {
+        match self {
+            AnyConf::Toml(v) => v.values(s, f),
+            AnyConf::Yaml(v) => v.values(s, f),
+            AnyConf::Json(v) => v.values(s, f),
+            AnyConf::Ini(v) => v.values(s, f),
+            AnyConf::Env(v) => v.values(s, f).map(|i| i.collect::<Vec<_>>().into_iter()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod anytests {
+    use super::*;
+    #[test]
+    fn test_load_by_ext() {
+        for f in &[
+            "test_data/test1.toml",
+            "test_data/test1.yaml",
+            "test_data/test1.json",
+            "test_data/test1.ini",
+        ] {
+            let l = load_config(f).unwrap();
+            assert_eq!(l.grab().conf("a.b.c").done(), Some("hello".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_load_first_mixed() {
+        let l = load_first_config(None, ["test_data/none.toml", "test_data/test1.yaml"]).unwrap();
+        assert_eq!(l.grab().conf("a.b.d").done(), Some("world".to_string()));
+        assert!(AnyConf::parse(Some("xml"), "").is_err());
+
+        let l = load_config("test_data/.env").unwrap();
+        assert_eq!(l.grab().env("TEST_A").done(), Some("hello".to_string()));
+    }
+
+    #[test]
+    fn test_report() {
+        for f in &[
+            "test_data/bad.toml",
+            "test_data/bad.yaml",
+            "test_data/bad.json",
+            "test_data/bad.ini",
+        ] {
+            let (v, r) = load_first_config_report(None, ["test_data/none.yaml", f]);
+            assert!(v.is_none());
+            assert_eq!(r.tried[0].fail, Some(LoadFail::NotFound));
+            match &r.tried[1].fail {
+                Some(LoadFail::Parse { line, .. }) => assert_eq!(*line, 3, "{}", f),
+                e => panic!("expected parse error in {}, got {:?}", f, e),
+            }
+            match load_first_config(Some(f), ["test_data/test1.toml"]) {
+                Err(ConfError::Parse { file, .. }) => assert_eq!(file, Some(PathBuf::from(f))),
+                r => panic!("expected parse error in {}, got {:?}", f, r),
+            }
+        }
+    }
+}
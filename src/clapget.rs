@@ -1,3 +1,4 @@
+use crate::value::parse_bool;
 use crate::{Filter, Getter};
 use clap::{ArgMatches, Values};
 
@@ -31,6 +32,37 @@ impl<'a, 'b> Getter<'a> for &'a ArgMatches<'b> {
         r.is_present(dot_last)
     }
 
+    /// Present is true, unless given a value such as "--x=off".
+    /// "--no-x" (an arg named "no-x" or "no_x") is false.
+    fn bool_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<bool> {
+        if f != Filter::Arg {
+            return None;
+        }
+        let (r, dot_last) = dig(self, s.as_ref())?;
+        if r.is_present(dot_last) {
+            return match r.value_of(dot_last) {
+                Some(v) => parse_bool(v),
+                None => Some(true),
+            };
+        }
+        if r.is_present(format!("no-{}", dot_last)) || r.is_present(format!("no_{}", dot_last)) {
+            return Some(false);
+        }
+        None
+    }
+
+    /// The number of occurrences, so "-vvv" is 3. None if not given.
+    fn count<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<u64> {
+        if f != Filter::Arg {
+            return None;
+        }
+        let (r, dot_last) = dig(self, s.as_ref())?;
+        match r.occurrences_of(dot_last) {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
     fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<&'a str> {
         if f != Filter::Arg {
             return None;
@@ -55,10 +87,7 @@ impl<'a, 'b> Getter<'a> for &'a ArgMatches<'b> {
             Some(v) => v,
             None => return false,
         };
-        match r.subcommand_matches(dot_last) {
-            Some(_) => true,
-            None => false,
-        }
+        r.subcommand_matches(dot_last).is_some()
     }
 }
 
@@ -81,10 +110,10 @@ mod tests {
 
         let mm = &m;
 
-        assert_eq!(mm.sub("a", Filter::Arg), false, "A");
+        assert!(!mm.sub("a", Filter::Arg), "A");
         assert_eq!(mm.grab().arg("a").done(), Some("hi"), "HI");
-        assert_eq!(mm.sub("subby", Filter::Arg), true, "--Sub Subby--");
+        assert!(mm.sub("subby", Filter::Arg), "--Sub Subby--");
         assert_eq!(mm.grab().arg("subby.b").done(), Some("world"), "C");
-        assert_eq!(mm.bool_flag("a", Filter::Arg), true);
+        assert!(mm.bool_flag("a", Filter::Arg));
     }
 }
@@ -1,6 +1,6 @@
 use crate::{Filter, Getter};
 use std::fmt::{Debug, Display};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub struct Holder<A, B> {
@@ -52,6 +52,20 @@ where
         self.a.bool_flag(s.as_ref(), f) || self.b.bool_flag(s, f)
     }
 
+    fn bool_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<bool> {
+        self.a
+            .bool_value(s.as_ref(), f)
+            .or_else(|| self.b.bool_value(s, f))
+    }
+
+    fn count<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<u64> {
+        self.a.count(s.as_ref(), f).or_else(|| self.b.count(s, f))
+    }
+
+    fn list<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Vec<String>> {
+        self.a.list(s.as_ref(), f).or_else(|| self.b.list(s, f))
+    }
+
     fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<A::Out> {
         self.a
             .value(s.as_ref(), f)
@@ -71,12 +85,23 @@ where
     fn local_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<PathBuf> {
         self.a
             .local_value(s.as_ref(), f)
-            .or_else(|| self.b.local_value(s, f).map(|r| r.into()))
+            .or_else(|| self.b.local_value(s, f))
     }
 
     fn sub<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
         self.a.sub(s.as_ref(), f) || self.b.sub(s, f)
     }
+
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        self.a.has(s.as_ref(), f) || self.b.has(s, f)
+    }
+
+    fn source_file<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<PathBuf> {
+        match self.a.value(s.as_ref(), f) {
+            Some(_) => self.a.source_file(s, f),
+            None => self.b.source_file(s, f),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -120,6 +145,12 @@ where
     fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
         self.g.bool_flag(s, f)
     }
+    fn bool_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<bool> {
+        self.g.bool_value(s, f)
+    }
+    fn count<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<u64> {
+        self.g.count(s, f)
+    }
     fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<R> {
         self.g.value(s, f).map(&self.f)
     }
@@ -138,11 +169,20 @@ where
     fn sub<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
         self.g.sub(s, f)
     }
+
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        self.g.has(s, f)
+    }
+
+    fn source_file<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<PathBuf> {
+        self.g.source_file(s, f)
+    }
 }
 
 #[derive(Debug)]
 pub struct Localizer<G> {
     local: PathBuf,
+    file: Option<PathBuf>,
     g: G,
 }
 
@@ -154,6 +194,35 @@ impl<G> Localizer<G> {
         Localizer {
             g,
             local: PathBuf::from(p),
+            file: None,
+        }
+    }
+
+    /// Localize to the directory containing the file, and remember the file
+    /// so it can be reported as the source of any values found.
+    pub fn from_file<P: AsRef<Path>>(g: G, p: P) -> Self {
+        let p = p.as_ref();
+        Localizer {
+            g,
+            local: PathBuf::from(p.parent().unwrap_or(Path::new("./"))),
+            file: Some(PathBuf::from(p)),
+        }
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    pub fn into_inner(self) -> G {
+        self.g
+    }
+
+    /// Convert the inner getter, keeping the location.
+    pub fn map<H, F: FnOnce(G) -> H>(self, f: F) -> Localizer<H> {
+        Localizer {
+            local: self.local,
+            file: self.file,
+            g: f(self.g),
         }
     }
 }
@@ -167,6 +236,12 @@ where
     fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
         self.g.bool_flag(s, f)
     }
+    fn bool_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<bool> {
+        self.g.bool_value(s, f)
+    }
+    fn count<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<u64> {
+        self.g.count(s, f)
+    }
     fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<G::Out> {
         self.g.value(s, f)
     }
@@ -175,14 +250,27 @@ where
         self.g.values(s, f)
     }
 
+    fn list<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Vec<String>> {
+        self.g.list(s, f)
+    }
+
     fn sub<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
         self.g.sub(s, f)
     }
 
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        self.g.has(s, f)
+    }
+
     fn local_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<PathBuf> {
         self.g.local_value(s, f).map(|iv| match iv.is_absolute() {
             true => iv,
             false => self.local.clone().join(iv),
         })
     }
+
+    fn source_file<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<PathBuf> {
+        self.g.value(s.as_ref(), f)?;
+        self.g.source_file(s, f).or_else(|| self.file.clone())
+    }
 }
@@ -0,0 +1,521 @@
+//! Deserialize a whole struct from a Getter.
+//!
+//! Each field is looked up like `grab().key(path)`, so args (with '.' replaced by '_')
+//! come before config values, and within each the order of the Holder stack is kept.
+//! Nested structs use dotted paths, Vec fields use `values()` for args and numbered
+//! entries for config lists, and numbers and bools are parsed from their string form.
+//!
+//! ```
+//! use clap_conf::prelude::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct B {
+//!     c: String,
+//!     d: Option<String>,
+//! }
+//! #[derive(Deserialize)]
+//! struct A {
+//!     b: B,
+//! }
+//! #[derive(Deserialize)]
+//! struct Conf {
+//!     a: A,
+//!     port: Option<u16>,
+//! }
+//!
+//! let matches = clap_app!(my_app=>
+//!     (@arg port:-p +takes_value "port")
+//! ).get_matches_from(vec!["my_app", "-p", "80"]);
+//! let cfg = with_toml_env(&matches, &["test_data/test1.toml"]);
+//!
+//! let c: Conf = cfg.deserialize().unwrap();
+//! assert_eq!(c.a.b.c, "hello");
+//! assert_eq!(c.a.b.d, Some("world".to_string()));
+//! assert_eq!(c.port, Some(80));
+//! ```
+
+use crate::replace::ConfError;
+use crate::{Filter, Getter};
+use serde::de::value::SeqDeserializer;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+impl de::Error for ConfError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ConfError::Message(msg.to_string())
+    }
+}
+
+/// A single string value, parsed as whatever type is asked for.
+pub struct Leaf {
+    key: String,
+    s: String,
+}
+
+impl Leaf {
+    fn parse<T: std::str::FromStr>(&self, tname: &str) -> Result<T, ConfError>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.s.trim().parse().map_err(|e| {
+            ConfError::Message(format!(
+                "Could not read '{}' as {} for {}: {}",
+                self.s, tname, self.key, e
+            ))
+        })
+    }
+}
+
+macro_rules! leaf_parse {
+    ($($dfn:ident => $vfn:ident : $t:ty,)*) => {
+        $(
+            fn $dfn<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfError> {
+                visitor.$vfn(self.parse::<$t>(stringify!($t))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Leaf {
+    type Error = ConfError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfError> {
+        visitor.visit_string(self.s)
+    }
+
+    leaf_parse! {
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+        deserialize_char => visit_char: char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConfError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConfError> {
+        visitor.visit_enum(self.s.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ConfError> for Leaf {
+    type Deserializer = Self;
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Deserializes the item at "path" from the Getter.
+/// An empty path is the whole config.
+pub struct GetterDe<'a, G: Getter<'a>> {
+    g: &'a G,
+    path: String,
+}
+
+impl<'a, G: Getter<'a>> GetterDe<'a, G> {
+    pub fn new(g: &'a G) -> Self {
+        GetterDe {
+            g,
+            path: String::new(),
+        }
+    }
+
+    fn child(&self, s: &str) -> Self {
+        let path = match self.path.as_str() {
+            "" => s.to_string(),
+            p => format!("{}.{}", p, s),
+        };
+        GetterDe { g: self.g, path }
+    }
+
+    fn leaf(&self) -> Result<Leaf, ConfError> {
+        let arg = self.path.replace('.', "_");
+        let s = match self.g.value(&arg, Filter::Arg) {
+            Some(v) => Some(v.to_string()),
+            //flags have no value, but can still be given on the command line
+            None => match self.g.bool_value(&arg, Filter::Arg) {
+                Some(b) => Some(b.to_string()),
+                None => self
+                    .g
+                    .value(&self.path, Filter::Conf)
+                    .map(|v| v.to_string()),
+            },
+        };
+        match s {
+            Some(s) => Ok(Leaf {
+                key: self.path.clone(),
+                s,
+            }),
+            None => Err(ConfError::Missing(vec![
+                (Filter::Arg, arg),
+                (Filter::Conf, self.path.clone()),
+            ])),
+        }
+    }
+
+    fn has(&self) -> bool {
+        let arg = self.path.replace('.', "_");
+        self.g.has(&arg, Filter::Arg)
+            || self.g.bool_value(&arg, Filter::Arg).is_some()
+            || self.g.has(&self.path, Filter::Conf)
+    }
+}
+
+macro_rules! to_leaf {
+    ($($dfn:ident)*) => {
+        $(
+            fn $dfn<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfError> {
+                self.leaf()?.$dfn(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, G: Getter<'a>> de::Deserializer<'de> for GetterDe<'a, G> {
+    type Error = ConfError;
+
+    to_leaf! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfError> {
+        match self.has() {
+            true => visitor.visit_some(self),
+            false => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConfError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConfError> {
+        self.leaf()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfError> {
+        //args and env vars give lists of strings, but config lists are read item by item,
+        //as values() skips items that are not strings for some file types
+        let arg = self.path.replace('.', "_");
+        let strs = match self.g.values(&arg, Filter::Arg) {
+            Some(it) => Some(it),
+            //a list in a config file has no value of its own, but an env var does
+            None => match self.g.value(&self.path, Filter::Conf) {
+                Some(_) => self.g.values(&self.path, Filter::Conf),
+                None => None,
+            },
+        };
+        if let Some(it) = strs {
+            let v: Vec<Leaf> = it
+                .map(|s| Leaf {
+                    key: self.path.clone(),
+                    s: s.to_string(),
+                })
+                .collect();
+            if !v.is_empty() {
+                return visitor.visit_seq(SeqDeserializer::new(v.into_iter()));
+            }
+        }
+        let mut v = Vec::new();
+        loop {
+            let c = self.child(&v.len().to_string());
+            if !c.has() {
+                break;
+            }
+            v.push(c);
+        }
+        visitor.visit_seq(SeqDeserializer::new(v.into_iter()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConfError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConfError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConfError> {
+        visitor.visit_map(Fields {
+            de: self,
+            fields: fields.iter(),
+            current: None,
+        })
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConfError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConfError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _: V) -> Result<V::Value, ConfError> {
+        Err(ConfError::Message(format!(
+            "Cannot list the keys of {}, use a struct instead of a map",
+            self.path
+        )))
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 bytes byte_buf
+    }
+}
+
+impl<'de, 'a, G: Getter<'a>> IntoDeserializer<'de, ConfError> for GetterDe<'a, G> {
+    type Deserializer = Self;
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Only the fields that can be found are given to the visitor,
+/// so missing Option and default fields behave as serde expects.
+struct Fields<'a, G: Getter<'a>> {
+    de: GetterDe<'a, G>,
+    fields: std::slice::Iter<'static, &'static str>,
+    current: Option<GetterDe<'a, G>>,
+}
+
+impl<'de, 'a, G: Getter<'a>> MapAccess<'de> for Fields<'a, G> {
+    type Error = ConfError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ConfError> {
+        for f in &mut self.fields {
+            let c = self.de.child(f);
+            if c.has() {
+                self.current = Some(c);
+                return seed.deserialize(f.into_deserializer()).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, ConfError> {
+        match self.current.take() {
+            Some(c) => seed.deserialize(c),
+            None => Err(ConfError::Mess("Value requested before key")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::clap_app;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Mode {
+        Fast,
+        Safe,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Conf {
+        name: String,
+        mode: Mode,
+        ratio: f64,
+        on: bool,
+        tags: Vec<String>,
+        nums: Vec<i32>,
+        servers: Vec<Server>,
+        missing: Option<String>,
+        #[serde(default)]
+        count: usize,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let tml: toml::Value = r#"
+            name = "test"
+            mode = "Safe"
+            ratio = 0.5
+            on = true
+            tags = ["a", "b"]
+            nums = [1, 2, 3]
+            [[servers]]
+            host = "one"
+            port = 80
+            [[servers]]
+            host = "two"
+            port = 8080
+        "#
+        .parse()
+        .unwrap();
+        let c: Conf = tml.deserialize().unwrap();
+        assert_eq!(
+            c,
+            Conf {
+                name: "test".to_string(),
+                mode: Mode::Safe,
+                ratio: 0.5,
+                on: true,
+                tags: vec!["a".to_string(), "b".to_string()],
+                nums: vec![1, 2, 3],
+                servers: vec![
+                    Server {
+                        host: "one".to_string(),
+                        port: 80
+                    },
+                    Server {
+                        host: "two".to_string(),
+                        port: 8080
+                    },
+                ],
+                missing: None,
+                count: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        let tml: toml::Value = "host = \"h\"\nport = \"80x\"".parse().unwrap();
+        let e = tml.deserialize::<Server>().unwrap_err();
+        assert!(e.to_string().contains("80x"), "{}", e);
+
+        let tml: toml::Value = "host = \"h\"".parse().unwrap();
+        assert!(tml.deserialize::<Server>().is_err());
+    }
+
+    #[test]
+    fn test_holder_order() {
+        let m = clap_app!(test_app=>
+            (@arg port: --port +takes_value "port")
+        )
+        .get_matches_from("test_app --port 9000".split(' '));
+        let tml: toml::Value = "host = \"h\"\nport = 80".parse().unwrap();
+        let h = crate::clap_env(&m).hold(tml);
+        let s: Server = h.deserialize().unwrap();
+        assert_eq!(s.port, 9000);
+        assert_eq!(s.host, "h");
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Flags {
+        verbose: bool,
+        quiet: Option<bool>,
+        #[serde(default)]
+        color: bool,
+    }
+
+    #[test]
+    fn test_arg_flags() {
+        let m = clap_app!(test_app=>
+            (@arg verbose: --verbose "verbose")
+            (@arg quiet: --quiet "quiet")
+            (@arg color: --color "color")
+        )
+        .get_matches_from("test_app --verbose".split(' '));
+        let tml: toml::Value = "verbose = false".parse().unwrap();
+        let f: Flags = crate::clap_env(&m).hold(tml).deserialize().unwrap();
+        assert_eq!(
+            f,
+            Flags {
+                verbose: true,
+                quiet: None,
+                color: false
+            }
+        );
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Lists {
+        names: Vec<String>,
+        nums: Vec<i64>,
+    }
+
+    #[test]
+    fn test_mixed_lists() {
+        let js: serde_json::Value =
+            serde_json::from_str(r#"{"names":["x",1,"y",true],"nums":[1,-2]}"#).unwrap();
+        let l: Lists = js.deserialize().unwrap();
+        assert_eq!(l.names, vec!["x", "1", "y", "true"]);
+        assert_eq!(l.nums, vec![1, -2]);
+
+        let tml: toml::Value = "names = [\"a\"]\nnums = [3, 4, 5]".parse().unwrap();
+        let l: Lists = tml.deserialize().unwrap();
+        assert_eq!(l.nums, vec![3, 4, 5]);
+
+        let m = clap_app!(test_app=>
+            (@arg nums: --nums +takes_value ... "nums")
+        )
+        .get_matches_from("test_app --nums 7 --nums 8".split(' '));
+        let l: Lists = crate::clap_env(&m).hold(tml).deserialize().unwrap();
+        assert_eq!(l.nums, vec![7, 8]);
+        assert_eq!(l.names, vec!["a"]);
+    }
+}
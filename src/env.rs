@@ -1,4 +1,8 @@
+use crate::paths::expand_path;
+use crate::replace::ConfError;
 use crate::{Filter, Getter};
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
 pub static EV: Enver = Enver {};
 
@@ -21,3 +25,224 @@ impl<'a> Getter<'a> for Enver {
         None
     }
 }
+
+/// Like Enver, but also answers Filter::Conf lookups by converting the dotted key
+/// to an environment variable name.
+/// With prefix "MYAPP_" and separator "__", "input.filename" checks "MYAPP_INPUT__FILENAME".
+#[derive(Clone, Debug)]
+pub struct PrefixEnver {
+    prefix: String,
+    sep: String,
+}
+
+impl PrefixEnver {
+    pub fn new<P: Into<String>, S: Into<String>>(prefix: P, sep: S) -> Self {
+        PrefixEnver {
+            prefix: prefix.into(),
+            sep: sep.into(),
+        }
+    }
+
+    /// The environment variable name used for a dotted config key.
+    pub fn env_name<S: AsRef<str>>(&self, s: S) -> String {
+        let parts: Vec<String> = s
+            .as_ref()
+            .split('.')
+            .map(|p| p.to_uppercase().replace('-', "_"))
+            .collect();
+        format!("{}{}", self.prefix, parts.join(&self.sep))
+    }
+
+    fn name<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
+        match f {
+            Filter::Env => Some(s.as_ref().to_string()),
+            Filter::Conf => Some(self.env_name(s)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Getter<'a> for PrefixEnver {
+    type Out = String;
+    type Iter = std::option::IntoIter<String>;
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
+        std::env::var(self.name(s, f)?).ok()
+    }
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Self::Iter> {
+        std::env::var(self.name(s, f)?)
+            .ok()
+            .map(|v| Some(v).into_iter())
+    }
+}
+
+/// The variables from a ".env" file, held in memory.
+/// Answers Filter::Env lookups without touching the process environment.
+/// Use `Enver{}.hold(env_file)` so real environment variables take priority.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnvFile {
+    map: BTreeMap<String, String>,
+}
+
+pub fn load_env_file<S: AsRef<str>>(s: S) -> Result<EnvFile, ConfError> {
+    let fname = expand_path(s.as_ref())?;
+    std::fs::read_to_string(&fname)?.parse()
+}
+
+fn read_quoted<I: Iterator<Item = char>>(it: &mut I, q: char) -> Result<String, ConfError> {
+    let mut res = String::new();
+    while let Some(c) = it.next() {
+        match c {
+            c if c == q => return Ok(res),
+            '\\' if q == '"' => match it.next().ok_or(ConfError::Syntax)? {
+                'n' => res.push('\n'),
+                't' => res.push('\t'),
+                'r' => res.push('\r'),
+                c => res.push(c),
+            },
+            c => res.push(c),
+        }
+    }
+    Err(ConfError::Syntax)
+}
+
+impl FromStr for EnvFile {
+    type Err = ConfError;
+    fn from_str(s: &str) -> Result<Self, ConfError> {
+        let mut map = BTreeMap::new();
+        let mut it = s.chars().peekable();
+        loop {
+            //read the key
+            let mut line = String::new();
+            for c in &mut it {
+                if c == '=' || c == '\n' {
+                    line.push(c);
+                    break;
+                }
+                line.push(c);
+            }
+            if line.is_empty() {
+                break;
+            }
+            let t = line.trim();
+            if t.is_empty() || t.starts_with('#') {
+                if !line.ends_with('\n') {
+                    for c in &mut it {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                continue;
+            }
+            if !t.ends_with('=') {
+                return Err(ConfError::Syntax);
+            }
+            let k = t[..t.len() - 1].trim();
+            let k = k.strip_prefix("export ").unwrap_or(k).trim();
+            if k.is_empty() || k.contains(char::is_whitespace) {
+                return Err(ConfError::Syntax);
+            }
+
+            //read the value
+            while let Some(' ') | Some('\t') = it.peek() {
+                it.next();
+            }
+            let v = match it.peek() {
+                Some('"') | Some('\'') => {
+                    let q = it.next().unwrap_or('"');
+                    let v = read_quoted(&mut it, q)?;
+                    for c in &mut it {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                    v
+                }
+                _ => {
+                    let mut v = String::new();
+                    for c in &mut it {
+                        if c == '\n' {
+                            break;
+                        }
+                        v.push(c);
+                    }
+                    if let Some(n) = v.find(" #") {
+                        v.truncate(n);
+                    }
+                    v.trim().to_string()
+                }
+            };
+            map.insert(k.to_string(), v);
+        }
+        Ok(EnvFile { map })
+    }
+}
+
+impl<'a> Getter<'a> for EnvFile {
+    type Out = String;
+    type Iter = std::option::IntoIter<String>;
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
+        if f == Filter::Env {
+            return self.map.get(s.as_ref()).cloned();
+        }
+        None
+    }
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Self::Iter> {
+        if f == Filter::Env {
+            return Some(self.map.get(s.as_ref()).cloned().into_iter());
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod envtests {
+    use super::*;
+    #[test]
+    fn test_env_file() {
+        let e: EnvFile = "# comment\nexport A=hello\nB = 'single {x}' \nC=\"multi\nline \\\"q\\\"\"\nD=plain # trailing\n\nE="
+            .parse()
+            .unwrap();
+        assert_eq!(e.value("A", Filter::Env), Some("hello".to_string()));
+        assert_eq!(e.value("B", Filter::Env), Some("single {x}".to_string()));
+        assert_eq!(
+            e.value("C", Filter::Env),
+            Some("multi\nline \"q\"".to_string())
+        );
+        assert_eq!(e.value("D", Filter::Env), Some("plain".to_string()));
+        assert_eq!(e.value("E", Filter::Env), Some("".to_string()));
+        assert_eq!(e.value("A", Filter::Conf), None);
+        assert!("A=\"open".parse::<EnvFile>().is_err());
+    }
+
+    #[test]
+    fn test_prefix() {
+        let p = PrefixEnver::new("MYAPP_", "__");
+        assert_eq!(p.env_name("input.file-name"), "MYAPP_INPUT__FILE_NAME");
+        assert_eq!(p.value("PATH", Filter::Env), std::env::var("PATH").ok());
+        assert_eq!(p.value("PATH", Filter::Arg), None);
+
+        let p = PrefixEnver::new("", "_");
+        assert_eq!(p.grab().conf("path").done(), std::env::var("PATH").ok());
+
+        //unset vars must not hide arrays further down
+        let tml: toml::Value = "[a]\nitems = [\"x\", \"y\"]".parse().unwrap();
+        let h = PrefixEnver::new("CLAP_CONF_PROBE_", "__").hold(tml);
+        let items: Vec<String> = h.grab_multi().conf("a.items").done().unwrap().collect();
+        assert_eq!(items, vec!["x", "y"]);
+        std::env::set_var("CLAP_CONF_PROBE_A__ITEMS", "z");
+        let items: Vec<String> = h.grab_multi().conf("a.items").done().unwrap().collect();
+        assert_eq!(items, vec!["z"]);
+    }
+
+    #[test]
+    fn test_env_first() {
+        let e: EnvFile = "PATH=nowhere\nCLAP_CONF_NOT_SET=there".parse().unwrap();
+        let h = Enver {}.hold(e);
+        assert_eq!(h.grab().env("PATH").done(), std::env::var("PATH").ok());
+        assert_eq!(
+            h.grab().env("CLAP_CONF_NOT_SET").done(),
+            Some("there".to_string())
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Opt in tracing of grabber lookups, for things like an "--explain-config" flag.
+//!
+//! ```
+//! use clap_conf::prelude::*;
+//! use clap_conf::explain::Explain;
+//!
+//! let matches = clap_app!(my_app=>
+//!     (@arg filename:-f +takes_value "the input filename")
+//! ).get_matches_from(vec!["my_app"]);
+//! let cfg = with_toml_env(&matches, &["test_data/test1.toml"]);
+//!
+//! let ex = Explain::new();
+//! let c = cfg.grab().explain(&ex).arg("filename").conf("a.b.c").env("MY_APP_FILE").done();
+//! assert_eq!(c, Some("hello".to_string()));
+//!
+//! //prints a table of every lookup tried
+//! println!("{}", ex);
+//! ```
+
+use crate::Filter;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The value was found here and used.
+    Used(String),
+    /// Nothing was found here.
+    Missing,
+    /// An earlier lookup already supplied the value, so this one was not tried.
+    Skipped,
+    /// A value was found here but failed validation.
+    Invalid(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// Which grab this step belongs to, starting at 1
+    pub grab: usize,
+    pub filter: Filter,
+    pub key: String,
+    pub outcome: Outcome,
+}
+
+/// A shared log of lookups. Cloning gives another handle to the same log.
+#[derive(Clone, Debug, Default)]
+pub struct Explain {
+    steps: Rc<RefCell<Vec<Step>>>,
+    grabs: Rc<Cell<usize>>,
+}
+
+impl Explain {
+    pub fn new() -> Self {
+        Explain::default()
+    }
+
+    /// Start a new grab, returning a Tracer that records into this log.
+    pub fn tracer(&self) -> Tracer {
+        self.grabs.set(self.grabs.get() + 1);
+        Tracer {
+            ex: self.clone(),
+            grab: self.grabs.get(),
+        }
+    }
+
+    pub fn steps(&self) -> Vec<Step> {
+        self.steps.borrow().clone()
+    }
+}
+
+impl fmt::Display for Explain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let steps = self.steps.borrow();
+        let rows: Vec<[String; 4]> = steps
+            .iter()
+            .map(|s| {
+                let res = match &s.outcome {
+                    Outcome::Used(v) => format!("used {:?}", v),
+                    Outcome::Missing => "missing".to_string(),
+                    Outcome::Skipped => "skipped".to_string(),
+                    Outcome::Invalid(m) => format!("invalid: {}", m),
+                };
+                [s.grab.to_string(), s.filter.to_string(), s.key.clone(), res]
+            })
+            .collect();
+        let head = ["grab", "filter", "key", "result"];
+        let mut w = [0; 3];
+        for (i, wi) in w.iter_mut().enumerate() {
+            *wi = rows
+                .iter()
+                .map(|r| r[i].len())
+                .chain(std::iter::once(head[i].len()))
+                .max()
+                .unwrap_or(0);
+        }
+        let line = |f: &mut fmt::Formatter, r: [&str; 4]| {
+            writeln!(
+                f,
+                "{:w0$}  {:w1$}  {:w2$}  {}",
+                r[0],
+                r[1],
+                r[2],
+                r[3],
+                w0 = w[0],
+                w1 = w[1],
+                w2 = w[2]
+            )
+        };
+        line(f, head)?;
+        for r in &rows {
+            line(f, [&r[0], &r[1], &r[2], &r[3]])?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the steps of a single grab into an Explain log.
+#[derive(Clone, Debug)]
+pub struct Tracer {
+    ex: Explain,
+    grab: usize,
+}
+
+impl Tracer {
+    pub fn record<S: AsRef<str>>(&self, f: Filter, s: S, outcome: Outcome) {
+        self.ex.steps.borrow_mut().push(Step {
+            grab: self.grab,
+            filter: f,
+            key: s.as_ref().to_string(),
+            outcome,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Getter;
+    #[test]
+    fn test_explain() {
+        let tml: toml::Value = "[a]\nb=\"red\"\nc=[\"x\"]".parse().unwrap();
+        let ex = Explain::new();
+        let r = tml
+            .grab()
+            .explain(&ex)
+            .conf("a.x")
+            .conf("a.b")
+            .conf("a.c")
+            .done();
+        assert_eq!(r, Some("red".to_string()));
+        tml.grab_multi().explain(&ex).conf("a.c").done();
+        tml.grab_local().explain(&ex).env("A").done();
+
+        let steps = ex.steps();
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps[0].outcome, Outcome::Missing);
+        assert_eq!(steps[1].outcome, Outcome::Used("red".to_string()));
+        assert_eq!(steps[2].outcome, Outcome::Skipped);
+        assert_eq!(steps[3].grab, 2);
+        assert_eq!(steps[4].filter, Filter::Env);
+
+        let table = ex.to_string();
+        let mut lines = table.lines();
+        assert_eq!(lines.next(), Some("grab  filter  key  result"));
+        assert_eq!(lines.next(), Some("1     conf    a.x  missing"));
+        assert_eq!(lines.next(), Some("1     conf    a.b  used \"red\""));
+    }
+}
@@ -1,9 +1,52 @@
+use std::ops::RangeBounds;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::str::FromStr;
+use std::time::Duration;
 
-use crate::replace::{replace_env, ConfError};
+use crate::explain::{Explain, Outcome, Tracer};
+use crate::replace::{replace_env, replace_keys, ConfError};
+use crate::units;
+use crate::value::ConfValue;
 use crate::{Filter, Getter};
 
+/// A value along with where it was found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sourced<T> {
+    pub value: T,
+    pub filter: Filter,
+    pub key: String,
+    /// The config file that supplied the value, if any.
+    pub file: Option<PathBuf>,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for Sourced<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} (from {} '{}'", self.value, self.filter, self.key)?;
+        if let Some(p) = &self.file {
+            write!(f, " in {}", p.display())?;
+        }
+        write!(f, ")")
+    }
+}
+
+type Check<'a, T> = Rc<dyn Fn(&T) -> Result<(), String> + 'a>;
+
+/// The validators added to a Grabber.
+struct Checks<'a, T>(Vec<Check<'a, T>>);
+
+impl<'a, T> Clone for Checks<'a, T> {
+    fn clone(&self) -> Self {
+        Checks(self.0.clone())
+    }
+}
+
+impl<'a, T> std::fmt::Debug for Checks<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Checks({})", self.0.len())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Grabber<'a, H>
 where
@@ -11,6 +54,12 @@ where
 {
     h: &'a H,
     res: Option<H::Out>,
+    tried: Vec<(Filter, String)>,
+    tr: Option<Tracer>,
+    checks: Checks<'a, H::Out>,
+    fall_through: bool,
+    err: Option<ConfError>,
+    skipped: Vec<(Filter, String)>,
 }
 
 impl<'a, H> Grabber<'a, H>
@@ -18,13 +67,131 @@ where
     H: Getter<'a>,
 {
     pub fn new(h: &'a H) -> Self {
-        Grabber { h, res: None }
+        Grabber {
+            h,
+            res: None,
+            tried: Vec::new(),
+            tr: None,
+            checks: Checks(Vec::new()),
+            fall_through: false,
+            err: None,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Record every lookup made by this grabber in the Explain log.
+    pub fn explain(mut self, ex: &Explain) -> Self {
+        self.tr = Some(ex.tracer());
+        self
     }
 
     pub fn op<S: AsRef<str>>(mut self, s: S, f: Filter) -> Self {
-        if self.res == None {
-            self.res = self.h.value(s, f);
+        self.lookup(s, f);
+        self
+    }
+
+    fn lookup<S: AsRef<str>>(&mut self, s: S, f: Filter) {
+        let failed = self.err.is_some() && !self.fall_through;
+        if self.res.is_some() || failed {
+            if let Some(t) = &self.tr {
+                t.record(f, s.as_ref(), Outcome::Skipped);
+            }
+            //kept in case a later check rejects the value found
+            self.skipped.push((f, s.as_ref().to_string()));
+            return;
+        }
+        self.tried.push((f, s.as_ref().to_string()));
+        self.res = self.h.value(s.as_ref(), f);
+        if let Some(t) = &self.tr {
+            match &self.res {
+                Some(r) => t.record(f, s, Outcome::Used(r.to_string())),
+                None => t.record(f, s, Outcome::Missing),
+            }
+        }
+        self.check_res();
+    }
+
+    /// Run the lookups skipped so far, once the value found has been rejected.
+    fn retry(&mut self) {
+        if self.res.is_some() || self.err.is_none() || !self.fall_through {
+            return;
+        }
+        for (f, k) in std::mem::take(&mut self.skipped) {
+            self.lookup(k, f);
         }
+    }
+
+    /// Run every check on the value found, dropping it if it fails.
+    fn check_res(&mut self) {
+        let r = match &self.res {
+            Some(r) => r,
+            None => return,
+        };
+        let m = match self.checks.0.iter().find_map(|c| c(r).err()) {
+            Some(m) => m,
+            None => return,
+        };
+        let e = self.invalid(r.to_string(), m.clone());
+        if let (Some(t), Some((f, k))) = (&self.tr, self.tried.last()) {
+            t.record(*f, k, Outcome::Invalid(m));
+        }
+        self.res = None;
+        if self.err.is_none() {
+            self.err = Some(e);
+        }
+        self.retry();
+    }
+
+    /// Reject values for which f returns an error.
+    /// Checks the value already found, and any found by later lookups.
+    /// Checks may come before or after the lookups.
+    /// An invalid value is an Invalid error from req, unless fall_through is set.
+    pub fn validate<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&H::Out) -> Result<(), String> + 'a,
+    {
+        self.checks.0.push(Rc::new(f));
+        self.check_res();
+        self
+    }
+
+    /// Only accept one of the given strings.
+    pub fn one_of<S: AsRef<str>>(self, opts: &[S]) -> Self {
+        let opts: Vec<String> = opts.iter().map(|s| s.as_ref().to_string()).collect();
+        self.validate(move |v| {
+            let v = v.to_string();
+            match opts.contains(&v) {
+                true => Ok(()),
+                false => Err(format!("expected one of: {}", opts.join(", "))),
+            }
+        })
+    }
+
+    /// Only accept values that parse, and are inside the range, eg `.range(1..=65535)`.
+    pub fn range<T, R>(self, r: R) -> Self
+    where
+        T: FromStr + PartialOrd + 'a,
+        T::Err: std::fmt::Display,
+        R: RangeBounds<T> + std::fmt::Debug + 'a,
+    {
+        self.validate(move |v| {
+            let n: T = v
+                .to_string()
+                .trim()
+                .parse()
+                .map_err(|e: T::Err| e.to_string())?;
+            match r.contains(&n) {
+                true => Ok(()),
+                false => Err(format!("not in range {:?}", r)),
+            }
+        })
+    }
+
+    /// Pass over invalid values and try the next source, rather than failing.
+    /// If no valid value is found, req still reports the first invalid one.
+    pub fn fall_through(mut self) -> Self {
+        self.fall_through = true;
+        self.retry();
         self
     }
 
@@ -39,10 +206,32 @@ where
         self.op(s, Filter::Arg)
     }
 
+    /// Look up a dotted key by convention:
+    /// the arg with '.' replaced by '_', then the config key.
+    /// Hold an env::PrefixEnver in the getter for the config key to also check the environment.
+    pub fn key<S: AsRef<str>>(self, s: S) -> Self {
+        let s = s.as_ref();
+        self.arg(s.replace('.', "_")).conf(s)
+    }
+
     pub fn done(self) -> Option<H::Out> {
         self.res
     }
 
+    /// Like done, but also reports the filter, key and file that supplied the value.
+    pub fn done_with_source(mut self) -> Option<Sourced<H::Out>> {
+        //once found no more lookups are tried, so the last one tried is the source
+        let value = self.res?;
+        let (filter, key) = self.tried.pop()?;
+        let file = self.h.source_file(&key, filter);
+        Some(Sourced {
+            value,
+            filter,
+            key,
+            file,
+        })
+    }
+
     pub fn def<V>(self, v: V) -> H::Out
     where
         H::Out: From<V>,
@@ -51,7 +240,36 @@ where
     }
 
     pub fn req(self) -> Result<H::Out, ConfError> {
-        self.res.ok_or("Item not supplied".into())
+        match (self.res, self.err) {
+            (Some(r), _) => Ok(r),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(ConfError::Missing(self.tried)),
+        }
+    }
+
+    /// Like done, but a value that failed validation is an error.
+    pub fn try_done(self) -> Result<Option<H::Out>, ConfError> {
+        match (self.res, self.err) {
+            (None, Some(e)) => Err(e),
+            (r, _) => Ok(r),
+        }
+    }
+
+    /// An Invalid error for the value found, naming where it came from.
+    fn invalid(&self, value: String, message: String) -> ConfError {
+        //once found no more lookups are tried, so the last one tried is the source
+        let (filter, key) = self
+            .tried
+            .last()
+            .cloned()
+            .unwrap_or((Filter::Other('?'), String::new()));
+        ConfError::Invalid {
+            file: self.h.source_file(&key, filter),
+            key,
+            filter,
+            value,
+            message,
+        }
     }
 }
 
@@ -72,7 +290,87 @@ where
             None => def,
         }
     }
+
+    /// Parse the value, failing if it is missing or does not parse.
+    pub fn t_req<T: FromStr>(self) -> Result<T, ConfError>
+    where
+        T::Err: std::fmt::Display,
+    {
+        match self.t_try()? {
+            Some(v) => Ok(v),
+            None => Err(ConfError::Missing(self.tried)),
+        }
+    }
+
+    /// Parse the value if there is one. Unlike t_done, a value that does not parse is an error.
+    pub fn t_try<T: FromStr>(&self) -> Result<Option<T>, ConfError>
+    where
+        T::Err: std::fmt::Display,
+    {
+        let r = match (&self.res, &self.err) {
+            (Some(r), _) => r.as_ref(),
+            (None, Some(e)) => return Err(e.clone()),
+            (None, None) => return Ok(None),
+        };
+        r.parse()
+            .map(Some)
+            .map_err(|e: T::Err| self.invalid(r.to_string(), e.to_string()))
+    }
 }
+/// Typed values, without a round trip through String for getters
+/// that return a ConfValue.
+impl<'a, H> Grabber<'a, H>
+where
+    H: Getter<'a>,
+    H::Out: Into<ConfValue>,
+{
+    pub fn int(self) -> Option<i64> {
+        self.res?.into().as_int()
+    }
+
+    pub fn float(self) -> Option<f64> {
+        self.res?.into().as_float()
+    }
+
+    pub fn boolean(self) -> Option<bool> {
+        self.res?.into().as_bool()
+    }
+
+    pub fn datetime(self) -> Option<toml::value::Datetime> {
+        self.res?.into().as_datetime()
+    }
+
+    fn unit<T, N, P>(mut self, num: N, parse: P) -> Result<Option<T>, ConfError>
+    where
+        N: Fn(f64) -> Result<T, String>,
+        P: Fn(&str) -> Result<T, String>,
+    {
+        let v: ConfValue = match (self.res.take(), self.err.take()) {
+            (Some(v), _) => v.into(),
+            (None, Some(e)) => return Err(e),
+            (None, None) => return Ok(None),
+        };
+        units::on_value(&v, num, parse)
+            .map(Some)
+            .map_err(|e| self.invalid(v.to_string(), e))
+    }
+
+    /// A duration such as "30s", "250ms" or "1h30m". Plain numbers are seconds.
+    pub fn duration(self) -> Result<Option<Duration>, ConfError> {
+        self.unit(units::secs_duration, units::parse_duration)
+    }
+
+    /// A byte size such as "512MiB" or "10k". Plain numbers are bytes.
+    pub fn bytes(self) -> Result<Option<u64>, ConfError> {
+        self.unit(units::num_bytes, units::parse_bytes)
+    }
+
+    /// A percentage such as "75%", given in percent, so "75%" is 75.0.
+    pub fn percent(self) -> Result<Option<f64>, ConfError> {
+        self.unit(units::num_percent, units::parse_percent)
+    }
+}
+
 impl<'a, H> Grabber<'a, H>
 where
     H: Getter<'a>,
@@ -82,6 +380,13 @@ where
         replace_env(self.res.ok_or("No Res")?.as_ref())
     }
 
+    /// Replace references to other keys and env vars, looked up in the same getter.
+    /// See replace::replace_keys.
+    pub fn rep_keys(self) -> Result<String, ConfError> {
+        let h = self.h;
+        replace_keys(self.req()?.as_ref(), h)
+    }
+
     pub fn ask<S: AsRef<str>>(self, s: S) -> Result<String, ConfError> {
         if let Some(r) = self.res {
             return Ok(r.as_ref().to_string());
@@ -95,7 +400,7 @@ where
     pub fn ask_def<S: AsRef<str>>(self, s: S, def: S) -> String {
         match self.ask(s) {
             Ok(r) => {
-                if r == "".to_string() {
+                if r.is_empty() {
                     return def.as_ref().to_string();
                 }
                 r
@@ -111,6 +416,8 @@ where
 {
     g: &'a G,
     res: Option<PathBuf>,
+    tried: Vec<(Filter, String)>,
+    tr: Option<Tracer>,
 }
 
 impl<'a, G> LocalGrabber<'a, G>
@@ -118,12 +425,34 @@ where
     G: Getter<'a>,
 {
     pub fn new(g: &'a G) -> Self {
-        LocalGrabber { g, res: None }
+        LocalGrabber {
+            g,
+            res: None,
+            tried: Vec::new(),
+            tr: None,
+        }
+    }
+
+    /// Record every lookup made by this grabber in the Explain log.
+    pub fn explain(mut self, ex: &Explain) -> Self {
+        self.tr = Some(ex.tracer());
+        self
     }
 
     pub fn op<S: AsRef<str>>(mut self, s: S, f: Filter) -> Self {
-        if self.res == None {
-            self.res = self.g.local_value(s, f);
+        if self.res.is_some() {
+            if let Some(t) = &self.tr {
+                t.record(f, s, Outcome::Skipped);
+            }
+            return self;
+        }
+        self.tried.push((f, s.as_ref().to_string()));
+        self.res = self.g.local_value(s.as_ref(), f);
+        if let Some(t) = &self.tr {
+            match &self.res {
+                Some(r) => t.record(f, s, Outcome::Used(r.display().to_string())),
+                None => t.record(f, s, Outcome::Missing),
+            }
         }
         self
     }
@@ -139,12 +468,17 @@ where
         self.op(s, Filter::Arg)
     }
 
+    pub fn key<S: AsRef<str>>(self, s: S) -> Self {
+        let s = s.as_ref();
+        self.arg(s.replace('.', "_")).conf(s)
+    }
+
     pub fn done(self) -> Option<PathBuf> {
         self.res
     }
 
     pub fn req(self) -> Result<PathBuf, ConfError> {
-        self.res.ok_or("Item not supplied".into())
+        self.res.ok_or(ConfError::Missing(self.tried))
     }
 
     pub fn def<V>(self, v: V) -> PathBuf
@@ -161,7 +495,8 @@ where
 {
     g: &'a G,
     res: Option<G::Iter>,
-    //_i: PhantomData<I>,
+    tried: Vec<(Filter, String)>,
+    tr: Option<Tracer>,
 }
 
 impl<'a, G> MultiGrabber<'a, G>
@@ -169,12 +504,35 @@ where
     G: Getter<'a>,
 {
     pub fn new(g: &'a G) -> Self {
-        MultiGrabber { g, res: None }
+        MultiGrabber {
+            g,
+            res: None,
+            tried: Vec::new(),
+            tr: None,
+        }
+    }
+
+    /// Record every lookup made by this grabber in the Explain log.
+    /// As the values are not read until later, used values are shown as "[..]"
+    pub fn explain(mut self, ex: &Explain) -> Self {
+        self.tr = Some(ex.tracer());
+        self
     }
 
     pub fn op<S: AsRef<str>>(mut self, s: S, f: Filter) -> Self {
-        if let None = self.res {
-            self.res = self.g.values(s, f);
+        if self.res.is_some() {
+            if let Some(t) = &self.tr {
+                t.record(f, s, Outcome::Skipped);
+            }
+            return self;
+        }
+        self.tried.push((f, s.as_ref().to_string()));
+        self.res = self.g.values(s.as_ref(), f);
+        if let Some(t) = &self.tr {
+            match &self.res {
+                Some(_) => t.record(f, s, Outcome::Used("[..]".to_string())),
+                None => t.record(f, s, Outcome::Missing),
+            }
         }
         self
     }
@@ -190,11 +548,210 @@ where
         self.op(s, Filter::Arg)
     }
 
+    pub fn key<S: AsRef<str>>(self, s: S) -> Self {
+        let s = s.as_ref();
+        self.arg(s.replace('.', "_")).conf(s)
+    }
+
     pub fn done(self) -> Option<G::Iter> {
         self.res
     }
 
     pub fn req(self) -> Result<G::Iter, ConfError> {
-        self.res.ok_or("Item not supplied".into())
+        self.res.ok_or(ConfError::Missing(self.tried))
+    }
+}
+
+/// Grabs an explicit true or false, so a later source can turn a flag off.
+/// A value that is not a recognised bool stops the search,
+/// and is an Invalid error from req or try_done.
+pub struct BoolGrabber<'a, G>
+where
+    G: Getter<'a>,
+{
+    g: &'a G,
+    res: Option<bool>,
+    tried: Vec<(Filter, String)>,
+    tr: Option<Tracer>,
+    err: Option<ConfError>,
+}
+
+impl<'a, G> BoolGrabber<'a, G>
+where
+    G: Getter<'a>,
+{
+    pub fn new(g: &'a G) -> Self {
+        BoolGrabber {
+            g,
+            res: None,
+            tried: Vec::new(),
+            tr: None,
+            err: None,
+        }
+    }
+
+    /// Record every lookup made by this grabber in the Explain log.
+    pub fn explain(mut self, ex: &Explain) -> Self {
+        self.tr = Some(ex.tracer());
+        self
+    }
+
+    pub fn op<S: AsRef<str>>(mut self, s: S, f: Filter) -> Self {
+        if self.res.is_some() || self.err.is_some() {
+            if let Some(t) = &self.tr {
+                t.record(f, s, Outcome::Skipped);
+            }
+            return self;
+        }
+        self.tried.push((f, s.as_ref().to_string()));
+        self.res = self.g.bool_value(s.as_ref(), f);
+        if self.res.is_none() {
+            //present, but not a bool
+            if let Some(v) = self.g.value(s.as_ref(), f) {
+                let message = "expected true or false".to_string();
+                if let Some(t) = &self.tr {
+                    t.record(f, s.as_ref(), Outcome::Invalid(message.clone()));
+                }
+                self.err = Some(ConfError::Invalid {
+                    key: s.as_ref().to_string(),
+                    filter: f,
+                    value: v.to_string(),
+                    file: self.g.source_file(s.as_ref(), f),
+                    message,
+                });
+                return self;
+            }
+        }
+        if let Some(t) = &self.tr {
+            match &self.res {
+                Some(r) => t.record(f, s, Outcome::Used(r.to_string())),
+                None => t.record(f, s, Outcome::Missing),
+            }
+        }
+        self
+    }
+
+    pub fn conf<S: AsRef<str>>(self, s: S) -> Self {
+        self.op(s, Filter::Conf)
+    }
+
+    pub fn env<S: AsRef<str>>(self, s: S) -> Self {
+        self.op(s, Filter::Env)
+    }
+    pub fn arg<S: AsRef<str>>(self, s: S) -> Self {
+        self.op(s, Filter::Arg)
+    }
+
+    pub fn key<S: AsRef<str>>(self, s: S) -> Self {
+        let s = s.as_ref();
+        self.arg(s.replace('.', "_")).conf(s)
+    }
+
+    pub fn done(self) -> Option<bool> {
+        self.res
+    }
+
+    pub fn def(self, v: bool) -> bool {
+        self.res.unwrap_or(v)
+    }
+
+    /// Like done, but a value that is not a bool is an error.
+    pub fn try_done(self) -> Result<Option<bool>, ConfError> {
+        match self.err {
+            Some(e) => Err(e),
+            None => Ok(self.res),
+        }
+    }
+
+    pub fn req(self) -> Result<bool, ConfError> {
+        match (self.res, self.err) {
+            (Some(b), _) => Ok(b),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(ConfError::Missing(self.tried)),
+        }
+    }
+}
+
+/// Grabs counts such as verbosity from every source given,
+/// so "-vvv", "log.level = 2" and an env var can be combined.
+/// Finish with first, to take the first found, or sum to add them together.
+pub struct CountGrabber<'a, G>
+where
+    G: Getter<'a>,
+{
+    g: &'a G,
+    found: Vec<u64>,
+    tried: Vec<(Filter, String)>,
+    tr: Option<Tracer>,
+}
+
+impl<'a, G> CountGrabber<'a, G>
+where
+    G: Getter<'a>,
+{
+    pub fn new(g: &'a G) -> Self {
+        CountGrabber {
+            g,
+            found: Vec::new(),
+            tried: Vec::new(),
+            tr: None,
+        }
+    }
+
+    /// Record every lookup made by this grabber in the Explain log.
+    /// As every source is looked up, all found values are shown as used.
+    pub fn explain(mut self, ex: &Explain) -> Self {
+        self.tr = Some(ex.tracer());
+        self
+    }
+
+    pub fn op<S: AsRef<str>>(mut self, s: S, f: Filter) -> Self {
+        self.tried.push((f, s.as_ref().to_string()));
+        let res = self.g.count(s.as_ref(), f);
+        if let Some(t) = &self.tr {
+            match res {
+                Some(r) => t.record(f, s, Outcome::Used(r.to_string())),
+                None => t.record(f, s, Outcome::Missing),
+            }
+        }
+        self.found.extend(res);
+        self
+    }
+
+    pub fn conf<S: AsRef<str>>(self, s: S) -> Self {
+        self.op(s, Filter::Conf)
+    }
+
+    pub fn env<S: AsRef<str>>(self, s: S) -> Self {
+        self.op(s, Filter::Env)
+    }
+    pub fn arg<S: AsRef<str>>(self, s: S) -> Self {
+        self.op(s, Filter::Arg)
+    }
+
+    pub fn key<S: AsRef<str>>(self, s: S) -> Self {
+        let s = s.as_ref();
+        self.arg(s.replace('.', "_")).conf(s)
+    }
+
+    /// The count from the first source that had one.
+    pub fn first(self) -> Option<u64> {
+        self.found.first().cloned()
+    }
+
+    /// All the counts found added together, None if no source had one.
+    /// The total stops at u64::MAX rather than overflowing.
+    pub fn sum(self) -> Option<u64> {
+        match self.found.is_empty() {
+            true => None,
+            false => Some(self.found.iter().fold(0, |a, b| a.saturating_add(*b))),
+        }
+    }
+
+    pub fn req(self) -> Result<u64, ConfError> {
+        match self.found.first() {
+            Some(n) => Ok(*n),
+            None => Err(ConfError::Missing(self.tried)),
+        }
     }
 }
@@ -0,0 +1,156 @@
+use crate::convert::Localizer;
+use crate::load::{first_or_err, load_first, LoadFail, LoadReport};
+use crate::paths::expand_path;
+use crate::replace::ConfError;
+use crate::{Filter, Getter};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The contents of an ini style file.
+/// Keys are stored as "section.key" so they can be found with the usual dotted lookup.
+/// Keys before the first section have no prefix.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ini {
+    map: BTreeMap<String, String>,
+}
+
+fn unquote(s: &str) -> &str {
+    let b = s.as_bytes();
+    if b.len() >= 2 && (b[0] == b'"' || b[0] == b'\'') && b[b.len() - 1] == b[0] {
+        return &s[1..s.len() - 1];
+    }
+    s
+}
+
+impl FromStr for Ini {
+    type Err = ConfError;
+    fn from_str(s: &str) -> Result<Self, ConfError> {
+        let mut map = BTreeMap::new();
+        let mut section = String::new();
+        for (n, line) in s.lines().enumerate() {
+            let err = |message: &str| ConfError::Parse {
+                file: None,
+                line: n + 1,
+                col: 1,
+                message: message.to_string(),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                if !line.ends_with(']') {
+                    return Err(err("unclosed section header"));
+                }
+                section = line[1..line.len() - 1].trim().to_string();
+                continue;
+            }
+            let split = line
+                .find(['=', ':'])
+                .ok_or_else(|| err("expected key = value"))?;
+            let k = line[..split].trim();
+            if k.is_empty() {
+                return Err(err("empty key"));
+            }
+            let v = unquote(line[split + 1..].trim());
+            let key = match section.as_str() {
+                "" => k.to_string(),
+                sec => format!("{}.{}", sec, k),
+            };
+            map.insert(key, v.to_string());
+        }
+        Ok(Ini { map })
+    }
+}
+
+pub fn load_ini<S: AsRef<str>>(s: S) -> Result<Localizer<Ini>, ConfError> {
+    let fname = expand_path(s.as_ref())?;
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v = fcont.parse::<Ini>().map_err(|e| e.in_file(&fname))?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// As load_ini, but keeps the reason for failing, including the position of parse errors.
+pub fn try_load_ini<S: AsRef<str>>(s: S) -> Result<Localizer<Ini>, LoadFail> {
+    let fname = expand_path(s.as_ref())?;
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v = fcont.parse::<Ini>()?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// Load the first ini file that can be loaded, along with a report of every candidate tried.
+pub fn load_first_ini_report<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> (Option<Localizer<Ini>>, LoadReport) {
+    load_first(a, i, |s| try_load_ini(s))
+}
+
+pub fn load_first_ini<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> Result<Localizer<Ini>, ConfError> {
+    first_or_err(a, load_first_ini_report(a, i))
+}
+
+impl<'a> Getter<'a> for Ini {
+    type Out = String;
+    type Iter = std::vec::IntoIter<String>;
+    fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        match self.value(s, f) {
+            Some(v) => v == "true",
+            None => false,
+        }
+    }
+
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        if f != Filter::Conf {
+            return false;
+        }
+        let s = s.as_ref();
+        let sec = format!("{}.", s);
+        self.map.keys().any(|k| k == s || k.starts_with(&sec))
+    }
+
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
+        if f != Filter::Conf {
+            return None;
+        }
+        self.map.get(s.as_ref()).cloned()
+    }
+
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Self::Iter> {
+        Some(vec![self.value(s, f)?].into_iter())
+    }
+}
+
+#[cfg(test)]
+mod initests {
+    use super::*;
+    use std::path::PathBuf;
+    #[test]
+    fn test_parse() {
+        let t: Ini =
+            "top = 1\n; comment\n[a.b]\nc = \"hello\"\n# another\nd: world\n[e]\nflag=true"
+                .parse()
+                .unwrap();
+        assert_eq!(t.value("top", Filter::Conf), Some("1".to_string()));
+        assert_eq!(t.value("a.b.c", Filter::Conf), Some("hello".to_string()));
+        assert_eq!(t.value("a.b.d", Filter::Conf), Some("world".to_string()));
+        assert_eq!(t.value("a.b.d", Filter::Env), None);
+        assert!(t.bool_flag("e.flag", Filter::Conf));
+        assert!("[broken\nk=v".parse::<Ini>().is_err());
+    }
+
+    #[test]
+    fn test_local() {
+        let l = load_ini("test_data/test1.ini").unwrap();
+        assert_eq!(l.grab().conf("a.b.c").done(), Some("hello".to_string()));
+        assert_eq!(
+            l.grab_local().conf("a.b.d").done(),
+            Some(PathBuf::from("test_data/world"))
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::convert::Localizer;
+use crate::load::{first_or_err, load_first, LoadFail, LoadReport};
+use crate::paths::expand_path;
+use crate::replace::ConfError;
+use crate::value::parse_bool;
+use crate::{Filter, Getter};
+use serde_json::Value;
+
+pub fn load_json<S: AsRef<str>>(s: S) -> Result<Localizer<Value>, ConfError> {
+    let fname = expand_path(s.as_ref())?;
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v =
+        serde_json::from_str::<Value>(&fcont).map_err(|e| ConfError::from(e).in_file(&fname))?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// As load_json, but keeps the reason for failing, including the position of parse errors.
+pub fn try_load_json<S: AsRef<str>>(s: S) -> Result<Localizer<Value>, LoadFail> {
+    let fname = expand_path(s.as_ref())?;
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v = serde_json::from_str::<Value>(&fcont).map_err(ConfError::from)?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// Load the first json file that can be loaded, along with a report of every candidate tried.
+pub fn load_first_json_report<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> (Option<Localizer<Value>>, LoadReport) {
+    load_first(a, i, |s| try_load_json(s))
+}
+
+pub fn load_first_json<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> Result<Localizer<Value>, ConfError> {
+    first_or_err(a, load_first_json_report(a, i))
+}
+
+fn dig<S: AsRef<str>, I: Iterator<Item = S>>(v: &Value, mut i: I) -> Option<&Value> {
+    match i.next() {
+        None => Some(v),
+        Some(s) => match v {
+            Value::Object(o) => dig(o.get(s.as_ref())?, i),
+            Value::Array(a) => dig(a.get(s.as_ref().parse::<usize>().ok()?)?, i),
+            _ => None,
+        },
+    }
+}
+
+impl<'a> Getter<'a> for Value {
+    type Out = String;
+    type Iter = std::vec::IntoIter<String>;
+    fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        (&self).bool_flag(s, f)
+    }
+
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        (&self).has(s, f)
+    }
+
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
+        match (&self).value(s, f)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<std::vec::IntoIter<String>> {
+        let res: Vec<String> = (&self)
+            .values(s, f)?
+            .filter_map(|v| v.as_str().map(|vr| vr.to_string()))
+            .collect();
+        Some(res.into_iter())
+    }
+}
+
+impl<'a> Getter<'a> for &'a Value {
+    type Out = &'a Value;
+    type Iter = std::slice::Iter<'a, Value>;
+    fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        if f != Filter::Conf {
+            return false;
+        }
+        match dig(self, s.as_ref().split('.')) {
+            Some(v) => v.as_bool().unwrap_or(false),
+            None => false,
+        }
+    }
+
+    fn bool_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<bool> {
+        match self.value(s, f)? {
+            Value::Bool(b) => Some(*b),
+            Value::String(s) => parse_bool(s),
+            Value::Number(n) => parse_bool(&n.to_string()),
+            _ => None,
+        }
+    }
+
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<&'a Value> {
+        if f != Filter::Conf {
+            return None;
+        }
+        dig(self, s.as_ref().split('.'))
+    }
+
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<std::slice::Iter<'a, Value>> {
+        if f != Filter::Conf {
+            return None;
+        }
+
+        let v = dig(self, s.as_ref().split('.'))?;
+
+        if let Value::Array(a) = v {
+            return Some(a.iter());
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod jsontests {
+    use super::*;
+    #[test]
+    fn test_load() {
+        let t: Value = serde_json::from_str(r#"{"a":{"b":{"c":{"car":"red"}}}}"#).unwrap();
+        let r = (&&t).value("a.b.c.car", Filter::Conf).unwrap();
+        assert_eq!(r.as_str().unwrap(), "red");
+
+        let t: Value =
+            serde_json::from_str(r#"{"a":{"b":[{"car":"red"},{"car":"green","n":3}]}}"#).unwrap();
+        let r = (&&t).value("a.b.1.car", Filter::Conf).unwrap();
+        assert_eq!(r.as_str().unwrap(), "green");
+        assert_eq!(t.value("a.b.1.n", Filter::Conf), Some("3".to_string()));
+    }
+
+    #[test]
+    fn test_iter() {
+        let t: Value = serde_json::from_str(r#"{"a":{"b":{"car":["red","green"]}}}"#).unwrap();
+        let mut r = (&&t)
+            .values("a.b.car", Filter::Conf)
+            .expect("Could not get values");
+        assert_eq!(r.next().unwrap().as_str().unwrap(), "red");
+        assert_eq!(r.next().unwrap().as_str().unwrap(), "green");
+    }
+}
@@ -0,0 +1,272 @@
+//! Several toml files merged into one, where each value remembers the file it came from.
+//!
+//! Files earlier in the list take precedence, as with load_first_toml,
+//! so list them most specific first: project, then user, then system.
+//! Tables are merged key by key, and arrays follow the ArrayMerge strategy.
+//!
+//! ```
+//! use clap_conf::prelude::*;
+//! use clap_conf::layered::ArrayMerge;
+//! use clap_conf::tomlget::load_all_toml;
+//!
+//! let cfg = load_all_toml(None, &["test_data/layer_user.toml", "test_data/layer_system.toml"], ArrayMerge::Append).unwrap();
+//!
+//! //set in both, the user file wins
+//! assert_eq!(cfg.grab().conf("server.port").done(), Some("9000".to_string()));
+//! //only in the system file
+//! assert_eq!(cfg.grab().conf("server.host").done(), Some("localhost".to_string()));
+//! //relative paths are local to the file they came from
+//! assert_eq!(cfg.grab_local().conf("server.root").done(), Some("test_data/www".into()));
+//! ```
+
+use crate::paths::expand_path;
+use crate::{Filter, Getter};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// How to merge an array found in more than one file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArrayMerge {
+    /// Use the array from the file with the highest precedence.
+    Replace,
+    /// Join the arrays, lower precedence items first.
+    Append,
+    /// As Append, but leave out items already present.
+    Union,
+}
+
+/// Merged toml values, with the file each came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layered {
+    value: Value,
+    /// dotted key to file, for every leaf and array, and for array items merged in later
+    sources: BTreeMap<String, PathBuf>,
+    files: Vec<PathBuf>,
+    arrays: ArrayMerge,
+}
+
+fn join_key(path: &str, k: &str) -> String {
+    match path.is_empty() {
+        true => k.to_string(),
+        false => format!("{}.{}", path, k),
+    }
+}
+
+impl Layered {
+    pub fn new(arrays: ArrayMerge) -> Self {
+        Layered {
+            value: Value::Table(Default::default()),
+            sources: BTreeMap::new(),
+            files: Vec::new(),
+            arrays,
+        }
+    }
+
+    /// The merged values.
+    pub fn merged(&self) -> &Value {
+        &self.value
+    }
+
+    /// The files merged, highest precedence first.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Merge in a file, with higher precedence than those already added.
+    pub fn add_over<P: AsRef<Path>>(&mut self, v: Value, file: P) {
+        let file = file.as_ref();
+        let mut base = std::mem::replace(&mut self.value, Value::Boolean(false));
+        self.overlay(&mut base, v, file, "");
+        self.value = base;
+        self.files.insert(0, file.to_path_buf());
+    }
+
+    fn overlay(&mut self, base: &mut Value, top: Value, file: &Path, path: &str) {
+        match (base, top) {
+            (Value::Table(b), Value::Table(t)) => {
+                for (k, v) in t {
+                    let p = join_key(path, &k);
+                    match b.get_mut(&k) {
+                        Some(bv) => self.overlay(bv, v, file, &p),
+                        None => {
+                            self.record(&v, file, &p);
+                            b.insert(k, v);
+                        }
+                    }
+                }
+            }
+            (Value::Array(b), Value::Array(t)) if self.arrays != ArrayMerge::Replace => {
+                //the array keeps its first source, items added here record their own
+                for v in t {
+                    if self.arrays == ArrayMerge::Append || !b.contains(&v) {
+                        let k = join_key(path, &b.len().to_string());
+                        self.sources.insert(k, file.to_path_buf());
+                        b.push(v);
+                    }
+                }
+            }
+            (b, t) => {
+                let under = format!("{}.", path);
+                self.sources
+                    .retain(|k, _| k != path && !k.starts_with(&under));
+                self.record(&t, file, path);
+                *b = t;
+            }
+        }
+    }
+
+    fn record(&mut self, v: &Value, file: &Path, path: &str) {
+        match v {
+            Value::Table(t) => {
+                for (k, v) in t {
+                    self.record(v, file, &join_key(path, k));
+                }
+            }
+            _ => {
+                self.sources.insert(path.to_string(), file.to_path_buf());
+            }
+        }
+    }
+
+    /// The file supplying a key, checking parent keys for items inside arrays.
+    fn file_for(&self, s: &str) -> Option<&PathBuf> {
+        let mut k = s;
+        loop {
+            if let Some(f) = self.sources.get(k) {
+                return Some(f);
+            }
+            k = &k[..k.rfind('.')?];
+        }
+    }
+}
+
+impl<'a> Getter<'a> for Layered {
+    type Out = String;
+    type Iter = std::vec::IntoIter<String>;
+    fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        self.value.bool_flag(s, f)
+    }
+
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        self.value.has(s, f)
+    }
+
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
+        self.value.value(s, f)
+    }
+
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Self::Iter> {
+        self.value.values(s, f)
+    }
+
+    /// Relative paths are joined to the directory of the file that supplied them.
+    fn local_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<PathBuf> {
+        let v = PathBuf::from(expand_path(&self.value(s.as_ref(), f)?).ok()?);
+        if v.is_absolute() {
+            return Some(v);
+        }
+        match self.file_for(s.as_ref()).and_then(|p| p.parent()) {
+            Some(d) => Some(d.join(v)),
+            None => Some(v),
+        }
+    }
+
+    fn source_file<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<PathBuf> {
+        if f != Filter::Conf || !self.has(s.as_ref(), f) {
+            return None;
+        }
+        self.file_for(s.as_ref()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tomlget::load_all_toml;
+
+    fn layered(arrays: ArrayMerge) -> Layered {
+        let files = [
+            "test_data/layer_user.toml",
+            "test_data/not_there.toml",
+            "test_data/layer_system.toml",
+        ];
+        load_all_toml(None, files, arrays).unwrap()
+    }
+
+    #[test]
+    fn test_arrays() {
+        let tags =
+            |l: Layered| -> Vec<String> { l.grab_multi().conf("tags").done().unwrap().collect() };
+        assert_eq!(tags(layered(ArrayMerge::Replace)), vec!["b", "c"]);
+        assert_eq!(tags(layered(ArrayMerge::Append)), vec!["a", "b", "b", "c"]);
+        assert_eq!(tags(layered(ArrayMerge::Union)), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_sources() {
+        let l = layered(ArrayMerge::Union);
+        let user = PathBuf::from("test_data/layer_user.toml");
+        let system = PathBuf::from("test_data/layer_system.toml");
+        assert_eq!(
+            l.source_file("server.port", Filter::Conf),
+            Some(user.clone())
+        );
+        assert_eq!(
+            l.source_file("server.host", Filter::Conf),
+            Some(system.clone())
+        );
+        //tags = ["a", "b", "c"], "a" and "b" from system, "c" only from user
+        assert_eq!(l.source_file("tags.0", Filter::Conf), Some(system.clone()));
+        assert_eq!(l.source_file("tags.1", Filter::Conf), Some(system.clone()));
+        assert_eq!(l.source_file("tags.2", Filter::Conf), Some(user.clone()));
+        assert_eq!(l.source_file("server.nope", Filter::Conf), None);
+        assert_eq!(l.files(), &[user.clone(), system]);
+        assert_eq!(
+            l.grab_local().conf("tags.2").done(),
+            Some(PathBuf::from("test_data/c"))
+        );
+
+        let l = layered(ArrayMerge::Append);
+        assert_eq!(l.source_file("tags.3", Filter::Conf), Some(user));
+
+        let mut l = Layered::new(ArrayMerge::Replace);
+        l.add_over("[a]\nb = 1\nc = 2".parse().unwrap(), "low/x.toml");
+        l.add_over("a = \"p\"".parse().unwrap(), "high/y.toml");
+        assert_eq!(
+            l.grab_local().conf("a").done(),
+            Some(PathBuf::from("high/p"))
+        );
+        assert_eq!(l.source_file("a.b", Filter::Conf), None);
+
+        let mut l = Layered::new(ArrayMerge::Append);
+        l.add_over("l = [\"x\"]".parse().unwrap(), "low/x.toml");
+        l.add_over("l = [\"y\"]".parse().unwrap(), "high/y.toml");
+        assert_eq!(
+            l.grab_local().conf("l.0").done(),
+            Some(PathBuf::from("low/x"))
+        );
+        assert_eq!(
+            l.grab_local().conf("l.1").done(),
+            Some(PathBuf::from("high/y"))
+        );
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let files = ["test_data/layer_user.toml", "test_data/bad.toml"];
+        match load_all_toml(None, files, ArrayMerge::Replace) {
+            Err(crate::ConfError::Parse { file, line, .. }) => {
+                assert_eq!(file, Some(PathBuf::from("test_data/bad.toml")));
+                assert_eq!(line, 3);
+            }
+            r => panic!("expected Parse error, got {:?}", r),
+        }
+        let e = load_all_toml(Some("test_data/not_there.toml"), files, ArrayMerge::Replace)
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Could not load file test_data/not_there.toml: not found"
+        );
+    }
+}
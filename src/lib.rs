@@ -30,20 +30,97 @@
 //!
 //! ```
 
+//lets the derive macro's ::clap_conf paths work inside this crate too
+extern crate self as clap_conf;
+
+pub mod anyget;
 pub mod clapget;
 pub mod convert;
+pub mod de;
 pub mod env;
+pub mod explain;
 pub mod grabber;
+pub mod iniget;
+pub mod jsonget;
+pub mod layered;
+pub mod load;
+pub mod paths;
 pub mod prelude;
 pub mod replace;
+pub mod report;
+pub mod spec;
 pub mod tomlget;
+pub mod units;
+pub mod value;
+pub mod yamlget;
 
 use crate::convert::Holder;
 use crate::convert::Localizer;
-use crate::replace::replace_env;
+use crate::value::{ConfValue, ValueEnver};
 use std::path::PathBuf;
 
 pub use clap::{clap_app, crate_version, ArgMatches, Values};
+pub use clap_conf_derive::ClapConf;
+pub use replace::ConfError;
+
+#[doc(hidden)]
+pub use clap;
+
+/// Generate clap args and their config and environment lookups from a single struct.
+///
+/// Usually derived, each field can have
+/// `#[conf(arg = "..", short = "f", long = "..", conf = "a.b", env = "..", default = "..", help = "..")]`,
+/// arg and conf default to the field name.
+/// Fields are parsed with `FromStr`, `bool` fields are flags,
+/// `Option` fields may be missing and `Vec` fields take multiple values.
+///
+/// ```
+/// use clap_conf::prelude::*;
+/// use clap_conf::ClapConf;
+///
+/// #[derive(ClapConf)]
+/// struct Opts {
+///     #[conf(arg = "filename", short = "f", conf = "a.b.c", env = "MY_APP_INPUT_FILE", help = "input")]
+///     filename: String,
+///     #[conf(default = "8080")]
+///     port: u16,
+///     verbose: bool,
+/// }
+///
+/// let app = Opts::add_args(clap::App::new("my_app"));
+/// let matches = app.get_matches_from(vec!["my_app", "--verbose"]);
+/// let cfg = with_toml_env(&matches, &["test_data/test1.toml"]);
+///
+/// let opts = Opts::from_getter(&cfg).unwrap();
+/// assert_eq!(opts.filename, "hello");
+/// assert_eq!(opts.port, 8080);
+/// assert!(opts.verbose);
+/// ```
+pub trait ClapConf: Sized {
+    fn add_args<'a, 'b>(app: clap::App<'a, 'b>) -> clap::App<'a, 'b>;
+    fn from_getter<'a, G: Getter<'a>>(g: &'a G) -> Result<Self, ConfError>;
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use crate::ConfError;
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn parse<T: FromStr>(s: &str, key: &str) -> Result<T, ConfError>
+    where
+        T::Err: Display,
+    {
+        s.parse()
+            .map_err(|e| ConfError::Message(format!("Could not parse '{}' for {}: {}", s, key, e)))
+    }
+
+    pub fn parse_flag(s: &str, key: &str) -> Result<bool, ConfError> {
+        crate::value::parse_bool(s).ok_or_else(|| {
+            ConfError::Message(format!("Could not parse '{}' for {}: not a bool", s, key))
+        })
+    }
+}
 
 pub fn clap_env<'a, G: Getter<'a>>(a: G) -> Holder<env::Enver, G>
 where
@@ -67,6 +144,52 @@ where
     env::Enver {}.hold(a).hold(tml)
 }
 
+/// Like with_toml_env, but config values keep their native types as a ConfValue.
+pub fn with_toml_env_typed<'a, G, S, IT>(
+    a: G,
+    it: IT,
+) -> Holder<Holder<ValueEnver, G>, Localizer<ConfValue>>
+where
+    G: Getter<'a, Out = &'a str>,
+    S: AsRef<str>,
+    IT: IntoIterator<Item = S>,
+{
+    let tml = tomlget::load_first_toml(a.value("config", Filter::Arg), it)
+        .map(|l| l.map(ConfValue::from))
+        .unwrap_or(Localizer::new(ConfValue::Table(Default::default()), ""));
+    let e: fn(String) -> ConfValue = ConfValue::from;
+    env::Enver {}.wrap(e).hold(a).hold(tml)
+}
+
+pub fn with_json_env<'a, G, S, IT>(
+    a: G,
+    it: IT,
+) -> Holder<Holder<env::Enver, G>, Localizer<serde_json::Value>>
+where
+    G: Getter<'a, Out = &'a str>,
+    S: AsRef<str>,
+    IT: IntoIterator<Item = S>,
+{
+    let js = jsonget::load_first_json(a.value("config", Filter::Arg), it)
+        .unwrap_or(Localizer::new(serde_json::Value::Null, ""));
+    env::Enver {}.hold(a).hold(js)
+}
+
+pub fn with_config_env<'a, G, S, IT>(
+    a: G,
+    it: IT,
+) -> Holder<Holder<env::Enver, G>, Localizer<anyget::AnyConf>>
+where
+    G: Getter<'a, Out = &'a str>,
+    S: AsRef<str>,
+    IT: IntoIterator<Item = S>,
+{
+    let cf = anyget::load_first_config(a.value("config", Filter::Arg), it).unwrap_or(
+        Localizer::new(anyget::AnyConf::Toml(toml::Value::Boolean(false)), ""),
+    );
+    env::Enver {}.hold(a).hold(cf)
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Filter {
     Conf,
@@ -75,6 +198,17 @@ pub enum Filter {
     Other(char),
 }
 
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Filter::Conf => write!(f, "conf"),
+            Filter::Arg => write!(f, "arg"),
+            Filter::Env => write!(f, "env"),
+            Filter::Other(c) => write!(f, "other({})", c),
+        }
+    }
+}
+
 pub trait Getter<'a>: Sized {
     type Iter: Iterator<Item = Self::Out>;
     type Out: PartialEq + std::fmt::Debug + std::fmt::Display;
@@ -83,7 +217,7 @@ pub trait Getter<'a>: Sized {
 
     fn local_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<PathBuf> {
         let v = self.value(s, f)?;
-        let s = replace_env(&v.to_string()).ok()?;
+        let s = paths::expand_path(&v.to_string()).ok()?;
         Some(PathBuf::from(s))
     }
 
@@ -91,8 +225,39 @@ pub trait Getter<'a>: Sized {
         self.value(s, f).is_some()
     }
 
+    /// An explicit true or false, None if not set or not a recognised bool.
+    /// See value::parse_bool for the accepted strings.
+    fn bool_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<bool> {
+        value::parse_bool(&self.value(s, f)?.to_string())
+    }
+
+    /// How many times a flag was given, or a number such as "log.level = 2" elsewhere.
+    fn count<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<u64> {
+        self.value(s, f)?.to_string().trim().parse().ok()
+    }
+
+    /// The values as strings, None if there are none,
+    /// as an unset env var gives an empty list, which counts as missing.
+    fn list<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Vec<String>> {
+        let v: Vec<String> = self.values(s, f)?.map(|v| v.to_string()).collect();
+        match v.is_empty() {
+            true => None,
+            false => Some(v),
+        }
+    }
+
     fn sub<S: AsRef<str>>(&self, _: S, _: Filter) -> bool {
-        return false;
+        false
+    }
+
+    /// Is there anything at this key, including tables and arrays that value cannot return.
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        self.value(s, f).is_some()
+    }
+
+    /// The file that supplied the value, if it came from a file.
+    fn source_file<S: AsRef<str>>(&self, _: S, _: Filter) -> Option<PathBuf> {
+        None
     }
 
     fn wrap<R2, F: Fn(Self::Out) -> R2>(self, f: F) -> convert::Wrapper<Self, F> {
@@ -112,6 +277,16 @@ pub trait Getter<'a>: Sized {
         grabber::Grabber::new(self)
     }
 
+    /// Grab a bool, where a later source can explicitly turn a flag off.
+    fn bool_grab(&'a self) -> grabber::BoolGrabber<'a, Self> {
+        grabber::BoolGrabber::new(self)
+    }
+
+    /// Grab a count, such as verbosity, from several sources.
+    fn count_grab(&'a self) -> grabber::CountGrabber<'a, Self> {
+        grabber::CountGrabber::new(self)
+    }
+
     fn grab_local(&'a self) -> grabber::LocalGrabber<'a, Self> {
         grabber::LocalGrabber::new(self)
     }
@@ -119,6 +294,11 @@ pub trait Getter<'a>: Sized {
     fn grab_multi(&'a self) -> grabber::MultiGrabber<'a, Self> {
         grabber::MultiGrabber::new(self)
     }
+
+    /// Fill a whole struct, see the de module for how fields are found.
+    fn deserialize<T: serde::de::DeserializeOwned>(&'a self) -> Result<T, ConfError> {
+        T::deserialize(de::GetterDe::new(self))
+    }
 }
 
 #[cfg(test)]
@@ -134,15 +314,10 @@ mod tests {
         //let ce = e.hold(&a).hold(tml);
 
         assert_eq!(ce.value("ss", Filter::Arg), None);
-        assert_eq!(
-            ce.value("PWD", Filter::Env),
-            Some("/home/matthew/scripts/rust/mlibs/clap_conf".to_string())
-        );
+        let pwd = std::env::var("PWD").ok();
+        assert_eq!(ce.value("PWD", Filter::Env), pwd);
 
-        assert_eq!(
-            ce.grab().env("PWD").done(),
-            Some("/home/matthew/scripts/rust/mlibs/clap_conf".to_string())
-        );
+        assert_eq!(ce.grab().env("PWD").done(), pwd);
 
         assert_eq!(ce.grab().conf("a.car").done(), Some("red".to_string()));
 
@@ -156,7 +331,392 @@ mod tests {
     #[test]
     fn test_grab() {
         let a = ArgMatches::new();
-        let r = with_toml_env(&a, &["test_data/test1.toml"]);
+        let r = with_toml_env(&a, ["test_data/test1.toml"]);
         assert_eq!(r.grab().conf("a.b.c").done(), Some("hello".to_string()));
     }
+
+    #[test]
+    fn test_source() {
+        let m = clap_app!(test_app=>
+            (@arg b: -b +takes_value "b")
+        )
+        .get_matches_from("test_app -b hi".split(' '));
+        let r = with_toml_env(&m, ["test_data/test1.toml"]);
+        let s = r.grab().arg("c").conf("a.b.c").done_with_source().unwrap();
+        assert_eq!(s.value, "hello");
+        assert_eq!(s.filter, Filter::Conf);
+        assert_eq!(s.key, "a.b.c");
+        assert_eq!(s.file, Some(PathBuf::from("test_data/test1.toml")));
+        assert_eq!(
+            s.to_string(),
+            "hello (from conf 'a.b.c' in test_data/test1.toml)"
+        );
+
+        let s = r.grab().arg("b").conf("a.b.c").done_with_source().unwrap();
+        assert_eq!(s.filter, Filter::Arg);
+        assert_eq!(s.file, None);
+        assert_eq!(r.grab().conf("a.b.x").done_with_source(), None);
+        match r.grab().arg("x").conf("a.b.x").req() {
+            Err(ConfError::Missing(v)) => assert_eq!(
+                v,
+                vec![
+                    (Filter::Arg, "x".to_string()),
+                    (Filter::Conf, "a.b.x".to_string())
+                ]
+            ),
+            r => panic!("expected Missing error, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_typed_errors() {
+        let m = clap_app!(test_app=>
+            (@arg port: --port +takes_value "port")
+        )
+        .get_matches_from("test_app --port 80x".split(' '));
+        let r = with_toml_env(&m, ["test_data/test1.toml"]);
+        match r.grab().arg("port").t_req::<u16>() {
+            Err(ConfError::Invalid {
+                key, filter, value, ..
+            }) => {
+                assert_eq!(key, "port");
+                assert_eq!(filter, Filter::Arg);
+                assert_eq!(value, "80x");
+            }
+            r => panic!("expected Invalid error, got {:?}", r),
+        }
+        let e = r.grab().conf("a.b.c").t_req::<u16>().unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Invalid value 'hello' for conf 'a.b.c' in test_data/test1.toml: invalid digit found in string"
+        );
+        assert_eq!(r.grab().conf("a.b.x").t_try::<u16>().unwrap(), None);
+        assert!(r.grab().conf("a.b.x").t_req::<u16>().is_err());
+        assert_eq!(r.grab().conf("a.b.x").arg("port").t_done::<u16>(), None);
+    }
+
+    #[test]
+    fn test_units() {
+        use std::time::Duration;
+        let m = clap_app!(test_app=>
+            (@arg timeout: --timeout +takes_value "timeout")
+        )
+        .get_matches_from("test_app --timeout 1m30s".split(' '));
+        let tml: toml::Value =
+            "timeout = 20\ncache = \"512MiB\"\nratio = \"75%\"\nbad = \"3 fortnights\"\nhuge = 1e100\nneg = -5"
+                .parse()
+                .unwrap();
+        let h = env::Enver {}
+            .wrap(value::ConfValue::from)
+            .hold(&m)
+            .hold(value::ConfValue::from(tml));
+        assert_eq!(
+            h.grab().arg("timeout").duration().unwrap(),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            h.grab().arg("nope").conf("timeout").duration().unwrap(),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(h.grab().conf("cache").bytes().unwrap(), Some(512 << 20));
+        assert_eq!(h.grab().conf("ratio").percent().unwrap(), Some(75.0));
+        assert_eq!(h.grab().conf("nope").bytes().unwrap(), None);
+        match h.grab().conf("bad").duration() {
+            Err(ConfError::Invalid { key, value, .. }) => {
+                assert_eq!(key, "bad");
+                assert_eq!(value, "3 fortnights");
+            }
+            r => panic!("expected Invalid error, got {:?}", r),
+        }
+        match h.grab().conf("huge").duration() {
+            Err(ConfError::Invalid { key, .. }) => assert_eq!(key, "huge"),
+            r => panic!("expected Invalid error, got {:?}", r),
+        }
+        match h.grab().conf("neg").bytes() {
+            Err(ConfError::Invalid { key, value, .. }) => {
+                assert_eq!(key, "neg");
+                assert_eq!(value, "-5");
+            }
+            r => panic!("expected Invalid error, got {:?}", r),
+        }
+        assert!(h.grab().conf("huge").bytes().is_err());
+        assert!(h.grab().conf("huge").percent().is_ok());
+    }
+
+    #[test]
+    fn test_bool_grab() {
+        let app = || {
+            clap_app!(test_app=>
+                (@arg color: --color "color")
+                (@arg no_color: --("no-color") "no color")
+            )
+        };
+        let tml: toml::Value = "[a]\ncolor = true\nsay = \"off\"\nodd = \"maybe\""
+            .parse()
+            .unwrap();
+        std::env::set_var("CLAP_CONF_TEST_BOOL_OFF", "false");
+
+        let m = app().get_matches_from("test_app --no-color".split(' '));
+        let h = clap_env(&m).hold(tml.clone());
+        assert_eq!(
+            h.bool_grab().arg("color").conf("a.color").done(),
+            Some(false)
+        );
+        assert_eq!(h.bool_grab().arg("nope").conf("a.say").done(), Some(false));
+        match h.bool_grab().conf("a.odd").conf("a.color").req() {
+            Err(ConfError::Invalid { key, value, .. }) => {
+                assert_eq!(key, "a.odd");
+                assert_eq!(value, "maybe");
+            }
+            r => panic!("expected Invalid error, got {:?}", r),
+        }
+        assert!(h.bool_grab().conf("a.odd").try_done().is_err());
+
+        let m = app().get_matches_from(vec!["test_app"]);
+        let h = clap_env(&m).hold(tml);
+        assert_eq!(
+            h.bool_grab()
+                .arg("color")
+                .env("CLAP_CONF_TEST_BOOL_OFF")
+                .conf("a.color")
+                .done(),
+            Some(false)
+        );
+        assert_eq!(h.bool_grab().arg("color").done(), None);
+        assert!(h.bool_grab().arg("color").def(true));
+    }
+
+    #[test]
+    fn test_count_grab() {
+        let m = clap_app!(test_app=>
+            (@arg v: -v +multiple "verbosity")
+            (@arg q: -q "quiet")
+        )
+        .get_matches_from("test_app -vvv".split(' '));
+        let tml: toml::Value = "[log]\nlevel = 2".parse().unwrap();
+        std::env::set_var("CLAP_CONF_TEST_VERBOSE", "1");
+        let h = clap_env(&m).hold(tml);
+        assert_eq!((&m).count("v", Filter::Arg), Some(3));
+        assert_eq!((&m).count("q", Filter::Arg), None);
+        let g = || {
+            h.count_grab()
+                .arg("v")
+                .conf("log.level")
+                .env("CLAP_CONF_TEST_VERBOSE")
+        };
+        assert_eq!(g().first(), Some(3));
+        assert_eq!(g().sum(), Some(6));
+        assert_eq!(h.count_grab().arg("q").conf("log.level").first(), Some(2));
+        assert_eq!(h.count_grab().arg("q").conf("log.nope").sum(), None);
+
+        std::env::set_var("CLAP_CONF_TEST_VERBOSE_MAX", u64::MAX.to_string());
+        assert_eq!(
+            h.count_grab()
+                .arg("v")
+                .env("CLAP_CONF_TEST_VERBOSE_MAX")
+                .sum(),
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let m = clap_app!(test_app=>
+            (@arg mode: --mode +takes_value "mode")
+            (@arg port: --port +takes_value "port")
+        )
+        .get_matches_from("test_app --mode slow --port 0".split(' '));
+        let tml: toml::Value = "mode = \"safe\"\nport = 8080".parse().unwrap();
+        let h = clap_env(&m).hold(tml);
+        match h
+            .grab()
+            .arg("mode")
+            .conf("mode")
+            .one_of(&["fast", "safe"])
+            .req()
+        {
+            Err(ConfError::Invalid {
+                key, filter, value, ..
+            }) => {
+                assert_eq!(key, "mode");
+                assert_eq!(filter, Filter::Arg);
+                assert_eq!(value, "slow");
+            }
+            r => panic!("expected Invalid error, got {:?}", r),
+        }
+        assert_eq!(
+            h.grab()
+                .one_of(&["fast", "safe"])
+                .fall_through()
+                .arg("mode")
+                .conf("mode")
+                .req()
+                .unwrap(),
+            "safe"
+        );
+        assert_eq!(
+            h.grab()
+                .range(1..=65535)
+                .fall_through()
+                .arg("port")
+                .conf("port")
+                .t_req::<u16>()
+                .unwrap(),
+            8080
+        );
+        let e = h
+            .grab()
+            .arg("port")
+            .range(1..=65535)
+            .try_done()
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Invalid value '0' for arg 'port': not in range 1..=65535"
+        );
+        let short = || {
+            h.grab()
+                .validate(|v| match v.len() {
+                    0..=3 => Ok(()),
+                    _ => Err("too long".to_string()),
+                })
+                .fall_through()
+                .arg("mode")
+                .conf("mode")
+        };
+        assert!(short().try_done().is_err());
+        assert_eq!(short().done(), None);
+
+        //checks placed after the lookups still fall through to skipped sources
+        let tml: toml::Value = "port = 0\ngood = 8080".parse().unwrap();
+        assert_eq!(
+            tml.grab()
+                .conf("port")
+                .conf("good")
+                .range(1..=65535)
+                .fall_through()
+                .t_req::<u16>()
+                .unwrap(),
+            8080
+        );
+        assert_eq!(
+            h.grab()
+                .arg("mode")
+                .conf("mode")
+                .fall_through()
+                .one_of(&["fast", "safe"])
+                .req()
+                .unwrap(),
+            "safe"
+        );
+        assert!(tml
+            .grab()
+            .conf("port")
+            .conf("good")
+            .range(1..=65535)
+            .req()
+            .is_err());
+    }
+
+    #[test]
+    fn test_local_home() {
+        let tml: toml::Value = "cache = \"~/c\"\ndata = \"{xdg_data}/d\"\nrel = \"r\""
+            .parse()
+            .unwrap();
+        let l = Localizer::new(tml, "/base");
+        let h = paths::home().unwrap();
+        assert_eq!(l.grab_local().conf("cache").done(), Some(h.join("c")));
+        assert_eq!(
+            l.grab_local().conf("data").done(),
+            Some(paths::xdg_data_home().unwrap().join("d"))
+        );
+        assert_eq!(
+            l.grab_local().conf("rel").done(),
+            Some(PathBuf::from("/base/r"))
+        );
+    }
+
+    #[test]
+    fn test_key() {
+        let m = clap_app!(test_app=>
+            (@arg a_b: --ab +takes_value "ab")
+        )
+        .get_matches_from("test_app --ab hi".split(' '));
+        let tml: toml::Value = "[a]\nb=\"red\"\nc=\"blue\"".parse().unwrap();
+        let r = env::PrefixEnver::new("CLAP_CONF_TEST_", "__")
+            .hold(&m)
+            .hold(tml);
+        assert_eq!(r.grab().key("a.b").done(), Some("hi".to_string()));
+        assert_eq!(r.grab().key("a.c").done(), Some("blue".to_string()));
+        assert_eq!(r.grab().key("a.d").done(), None);
+    }
+
+    #[derive(ClapConf, Debug, PartialEq)]
+    struct Opts {
+        #[conf(short = "n", conf = "a.n", help = "a number")]
+        num: Option<i32>,
+        #[conf(arg = "item", conf = "a.items")]
+        items: Vec<String>,
+        #[conf(conf = "a.b.c", env = "CLAP_CONF_NOT_SET", default = "x")]
+        word: String,
+        #[conf(default = "2.5")]
+        ratio: f64,
+        on: bool,
+        #[conf(env = "CLAP_CONF_TEST_DERIVE_LIST", default = "d,e")]
+        defs: Vec<String>,
+        #[conf(env = "CLAP_CONF_TEST_DERIVE_FLAG")]
+        flag: bool,
+        #[conf(default = "yes")]
+        dflag: bool,
+    }
+
+    #[test]
+    fn test_derive() {
+        let app = Opts::add_args(clap::App::new("test_app"));
+        let m = app.get_matches_from("test_app -n 3 --item a --item b".split(' '));
+        std::env::set_var("CLAP_CONF_TEST_DERIVE_FLAG", "on");
+        let tml: toml::Value = "[a]\nitems=[\"c\"]\n[a.b]\nc=\"hi\"".parse().unwrap();
+        let h = clap_env(&m).hold(tml);
+        assert_eq!(
+            Opts::from_getter(&h).unwrap(),
+            Opts {
+                num: Some(3),
+                items: vec!["a".to_string(), "b".to_string()],
+                word: "hi".to_string(),
+                ratio: 2.5,
+                on: false,
+                defs: vec!["d".to_string(), "e".to_string()],
+                flag: true,
+                dflag: true,
+            }
+        );
+
+        let m = Opts::add_args(clap::App::new("test_app"))
+            .get_matches_from("test_app -n 3x --on".split(' '));
+        assert!(Opts::from_getter(&clap_env(&m)).is_err());
+
+        let m = Opts::add_args(clap::App::new("test_app")).get_matches_from(vec!["test_app"]);
+        let tml: toml::Value = "dflag = \"maybe\"".parse().unwrap();
+        match Opts::from_getter(&clap_env(&m).hold(tml)) {
+            Err(ConfError::Invalid {
+                key, filter, value, ..
+            }) => {
+                assert_eq!(key, "dflag");
+                assert_eq!(filter, Filter::Conf);
+                assert_eq!(value, "maybe");
+            }
+            r => panic!("expected Invalid error, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_grab_json() {
+        let a = ArgMatches::new();
+        let r = with_json_env(&a, ["test_data/test1.json"]);
+        assert_eq!(r.grab().conf("a.b.d").done(), Some("world".to_string()));
+        assert_eq!(
+            r.grab_local().conf("a.b.c").done(),
+            Some(PathBuf::from("test_data/hello"))
+        );
+    }
 }
@@ -0,0 +1,161 @@
+use crate::replace::ConfError;
+use std::fmt;
+
+/// Why a candidate config file could not be used.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadFail {
+    NotFound,
+    PermissionDenied,
+    /// line and col start at 1
+    Parse {
+        line: usize,
+        col: usize,
+        message: String,
+    },
+    Other(String),
+}
+
+impl fmt::Display for LoadFail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadFail::NotFound => write!(f, "not found"),
+            LoadFail::PermissionDenied => write!(f, "permission denied"),
+            LoadFail::Parse { line, col, message } => write!(
+                f,
+                "parse error at line {} column {}: {}",
+                line, col, message
+            ),
+            LoadFail::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadFail {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => LoadFail::NotFound,
+            std::io::ErrorKind::PermissionDenied => LoadFail::PermissionDenied,
+            _ => LoadFail::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<ConfError> for LoadFail {
+    fn from(e: ConfError) -> Self {
+        match e {
+            ConfError::Parse {
+                line, col, message, ..
+            } => LoadFail::Parse { line, col, message },
+            e => LoadFail::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<LoadFail> for ConfError {
+    fn from(e: LoadFail) -> Self {
+        match e {
+            LoadFail::Parse { line, col, message } => ConfError::Parse {
+                file: None,
+                line,
+                col,
+                message,
+            },
+            e => ConfError::LoadError(e.to_string()),
+        }
+    }
+}
+
+/// A candidate path, and why it failed to load, if it did.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tried {
+    pub path: String,
+    pub fail: Option<LoadFail>,
+}
+
+/// Every candidate tried by a loader, in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadReport {
+    pub tried: Vec<Tried>,
+}
+
+impl LoadReport {
+    /// The path of the file that was loaded if any.
+    pub fn loaded(&self) -> Option<&str> {
+        self.tried
+            .iter()
+            .find(|t| t.fail.is_none())
+            .map(|t| t.path.as_str())
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for t in &self.tried {
+            match &t.fail {
+                Some(e) => writeln!(f, "{}: {}", t.path, e)?,
+                None => writeln!(f, "{}: loaded", t.path)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Try each candidate in turn with the loader, stopping at the first that loads.
+/// If "sel" is supplied (normally from a "config" arg), only that file is tried.
+/// Events are sent to the log facade rather than printed.
+pub fn load_first<T, S, IT, F>(sel: Option<&str>, it: IT, f: F) -> (Option<T>, LoadReport)
+where
+    S: AsRef<str>,
+    IT: IntoIterator<Item = S>,
+    F: Fn(&str) -> Result<T, LoadFail>,
+{
+    let mut report = LoadReport::default();
+    let mut attempt = |s: &str| {
+        let res = f(s);
+        report.tried.push(Tried {
+            path: s.to_string(),
+            fail: res.as_ref().err().cloned(),
+        });
+        res
+    };
+    if let Some(m) = sel {
+        log::info!("config selected = {}", m);
+        return match attempt(m) {
+            Ok(v) => (Some(v), report),
+            Err(e) => {
+                log::error!("Could not load selected config file {}: {}", m, e);
+                (None, report)
+            }
+        };
+    }
+    for s in it {
+        match attempt(s.as_ref()) {
+            Ok(v) => {
+                log::info!("config loaded from {}", s.as_ref());
+                return (Some(v), report);
+            }
+            Err(e) => log::debug!("config candidate {} skipped: {}", s.as_ref(), e),
+        }
+    }
+    log::warn!("no config file could be loaded");
+    (None, report)
+}
+
+/// The value from load_first, or if the selected file failed, why.
+pub(crate) fn first_or_err<T>(
+    sel: Option<&str>,
+    (v, r): (Option<T>, LoadReport),
+) -> Result<T, ConfError> {
+    match (v, sel, r.tried.last()) {
+        (Some(v), _, _) => Ok(v),
+        (
+            None,
+            Some(_),
+            Some(Tried {
+                path,
+                fail: Some(e),
+            }),
+        ) => Err(ConfError::from(e.clone()).in_file(path)),
+        _ => Err("could not load".into()),
+    }
+}
@@ -0,0 +1,355 @@
+//! Expanding paths, with "~" for the home directory and placeholders for
+//! the standard directories, so candidate lists can be written as
+//! `"~/.myapp.toml"` or `"{xdg_config}/myapp/config.toml"`.
+//!
+//! | placeholder    | value                                         |
+//! |----------------|-----------------------------------------------|
+//! | `{home}`       | $HOME                                         |
+//! | `{xdg_config}` | $XDG_CONFIG_HOME, or ~/.config                |
+//! | `{xdg_data}`   | $XDG_DATA_HOME, or ~/.local/share             |
+//! | `{xdg_cache}`  | $XDG_CACHE_HOME, or ~/.cache                  |
+//! | `{exe_dir}`    | the directory holding the running executable  |
+//!
+//! Anything else is an environment variable, as in replace::replace_env.
+//!
+//! discover builds the usual list of places to look for an app's config file.
+//!
+//! ```
+//! use clap_conf::prelude::*;
+//! use clap_conf::paths::discover;
+//!
+//! let matches = clap_app!(my_app=>
+//!     (@arg config: -c +takes_value "config file")
+//! ).get_matches_from(vec!["my_app"]);
+//!
+//! let found = discover("my_app");
+//! //print every candidate considered, and whether it exists
+//! println!("{}", found);
+//! let cfg = with_toml_env(&matches, found);
+//! ```
+
+use crate::replace::{env_var, replace, ConfError};
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+fn process_env(v: &str) -> Option<OsString> {
+    std::env::var_os(v)
+}
+
+fn home_in<E: Fn(&str) -> Option<OsString>>(env: &E) -> Option<PathBuf> {
+    env("HOME").filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+pub fn home() -> Option<PathBuf> {
+    home_in(&process_env)
+}
+
+/// An XDG base directory, falling back to a path under home.
+/// As the spec says, relative values are ignored.
+fn xdg<E: Fn(&str) -> Option<OsString>>(var: &str, def: &str, env: &E) -> Option<PathBuf> {
+    match env(var).map(PathBuf::from) {
+        Some(p) if p.is_absolute() => Some(p),
+        _ => home_in(env).map(|h| h.join(def)),
+    }
+}
+
+pub fn xdg_config_home() -> Option<PathBuf> {
+    xdg("XDG_CONFIG_HOME", ".config", &process_env)
+}
+
+pub fn xdg_data_home() -> Option<PathBuf> {
+    xdg("XDG_DATA_HOME", ".local/share", &process_env)
+}
+
+pub fn xdg_cache_home() -> Option<PathBuf> {
+    xdg("XDG_CACHE_HOME", ".cache", &process_env)
+}
+
+pub fn exe_dir() -> Option<PathBuf> {
+    Some(std::env::current_exe().ok()?.parent()?.to_path_buf())
+}
+
+/// The value of a placeholder, or an environment variable.
+pub fn placeholder(v: &str) -> Result<String, ConfError> {
+    let p = match v {
+        "home" => home(),
+        "xdg_config" => xdg_config_home(),
+        "xdg_data" => xdg_data_home(),
+        "xdg_cache" => xdg_cache_home(),
+        "exe_dir" => exe_dir(),
+        v => return env_var(v),
+    };
+    p.map(|p| p.display().to_string())
+        .ok_or_else(|| ConfError::Message(format!("Could not find a directory for {{{}}}", v)))
+}
+
+/// Expand a leading "~", placeholders and environment variables in a path.
+pub fn expand_path(s: &str) -> Result<String, ConfError> {
+    let (pre, rest) = match s.strip_prefix('~') {
+        Some(r) if r.is_empty() || r.starts_with('/') => {
+            let h = home().ok_or_else(|| ConfError::Message("HOME is not set".to_string()))?;
+            (h.display().to_string(), r)
+        }
+        _ => (String::new(), s),
+    };
+    Ok(pre + &replace(rest, &placeholder)?)
+}
+
+/// Why a path is a candidate.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Origin {
+    /// Named by the environment variable
+    EnvVar(String),
+    /// The current directory or one of its parents
+    Cwd,
+    XdgConfigHome,
+    XdgConfigDirs,
+    Etc,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::EnvVar(v) => write!(f, "${}", v),
+            Origin::Cwd => write!(f, "cwd"),
+            Origin::XdgConfigHome => write!(f, "XDG_CONFIG_HOME"),
+            Origin::XdgConfigDirs => write!(f, "XDG_CONFIG_DIRS"),
+            Origin::Etc => write!(f, "/etc"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub origin: Origin,
+}
+
+impl Candidate {
+    pub fn exists(&self) -> bool {
+        self.path.is_file()
+    }
+}
+
+/// The candidate config files for an app, in the order they should be tried.
+/// Iterate over it, or pass it to a load_first function, to get the paths as Strings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Discovery {
+    pub candidates: Vec<Candidate>,
+}
+
+impl Discovery {
+    fn push<P: Into<PathBuf>>(&mut self, p: P, origin: Origin) {
+        self.candidates.push(Candidate {
+            path: p.into(),
+            origin,
+        });
+    }
+
+    /// The candidates that exist.
+    pub fn existing(&self) -> impl Iterator<Item = &Candidate> {
+        self.candidates.iter().filter(|c| c.exists())
+    }
+}
+
+/// A real path as a string that expand_path gives back unchanged,
+/// escaping '~' at the start, and '{', '}', '$' and '\\' anywhere.
+/// None if the path is not valid unicode.
+pub fn escape_path(p: &Path) -> Option<String> {
+    let s = p.to_str()?;
+    let mut res = String::new();
+    if s.starts_with('~') {
+        res.push('\\');
+    }
+    for c in s.chars() {
+        if let '{' | '}' | '$' | '\\' = c {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    Some(res)
+}
+
+/// The paths are escaped, as the loaders expand placeholders in what they are given.
+/// Paths that are not valid unicode cannot be loaded by name, so are left out.
+impl IntoIterator for Discovery {
+    type Item = String;
+    type IntoIter = std::vec::IntoIter<String>;
+    fn into_iter(self) -> Self::IntoIter {
+        let v: Vec<String> = self
+            .candidates
+            .into_iter()
+            .filter_map(|c| {
+                let r = escape_path(&c.path);
+                if r.is_none() {
+                    log::debug!("config candidate {:?} is not unicode, skipped", c.path);
+                }
+                r
+            })
+            .collect();
+        v.into_iter()
+    }
+}
+
+impl fmt::Display for Discovery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in &self.candidates {
+            let found = match c.exists() {
+                true => "found",
+                false => "missing",
+            };
+            writeln!(f, "{} ({}): {}", c.path.display(), c.origin, found)?;
+        }
+        Ok(())
+    }
+}
+
+/// The standard config locations for an app called "name", in order:
+///
+/// * $NAME_CONFIG, (name uppercased with '-' as '_')
+/// * ./.name.toml, and the same in each parent directory, nearest first
+/// * $XDG_CONFIG_HOME/name/config.toml
+/// * name/config.toml in each of $XDG_CONFIG_DIRS, by default /etc/xdg
+/// * /etc/name/config.toml
+pub fn discover(name: &str) -> Discovery {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    discover_from(name, cwd)
+}
+
+/// As discover, but walking up from the given directory rather than the current one.
+pub fn discover_from<P: AsRef<Path>>(name: &str, dir: P) -> Discovery {
+    discover_with(name, dir, process_env)
+}
+
+/// As discover_from, but reading environment variables with env
+/// rather than from the process environment.
+pub fn discover_with<P, E>(name: &str, dir: P, env: E) -> Discovery
+where
+    P: AsRef<Path>,
+    E: Fn(&str) -> Option<OsString>,
+{
+    let mut res = Discovery::default();
+    let var = format!("{}_CONFIG", name.to_uppercase().replace('-', "_"));
+    if let Some(p) = env(&var).filter(|p| !p.is_empty()) {
+        res.push(p, Origin::EnvVar(var));
+    }
+    let dot = format!(".{}.toml", name);
+    for d in dir.as_ref().ancestors() {
+        res.push(d.join(&dot), Origin::Cwd);
+    }
+    let in_dir = Path::new(name).join("config.toml");
+    if let Some(d) = xdg("XDG_CONFIG_HOME", ".config", &env) {
+        res.push(d.join(&in_dir), Origin::XdgConfigHome);
+    }
+    let dirs = env("XDG_CONFIG_DIRS")
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| OsString::from("/etc/xdg"));
+    for d in std::env::split_paths(&dirs).filter(|d| d.is_absolute()) {
+        res.push(d.join(&in_dir), Origin::XdgConfigDirs);
+    }
+    res.push(Path::new("/etc").join(&in_dir), Origin::Etc);
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_expand() {
+        let h = home().unwrap().display().to_string();
+        assert_eq!(expand_path("~/a.toml").unwrap(), format!("{}/a.toml", h));
+        assert_eq!(expand_path("~").unwrap(), h);
+        assert_eq!(expand_path("a/~/b").unwrap(), "a/~/b");
+        assert_eq!(
+            expand_path("{xdg_config}/app.toml").unwrap(),
+            xdg_config_home()
+                .unwrap()
+                .join("app.toml")
+                .display()
+                .to_string()
+        );
+        assert_eq!(
+            PathBuf::from(expand_path("{exe_dir}").unwrap()),
+            exe_dir().unwrap()
+        );
+        assert!(expand_path("{CLAP_CONF_TEST_NOT_SET}/a").is_err());
+    }
+
+    fn fake_env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: Vec<(String, OsString)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), OsString::from(v)))
+            .collect();
+        move |k| vars.iter().find(|(vk, _)| vk == k).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_discover() {
+        let env = fake_env(&[("HOME", "/home/me"), ("XDG_CONFIG_DIRS", "/a/xdg:rel/xdg")]);
+        let d = discover_with("walk-test", "test_data/a/b", &env);
+        let paths: Vec<String> = d.clone().into_iter().collect();
+        assert_eq!(paths[0], "test_data/a/b/.walk-test.toml");
+        assert_eq!(paths[2], "test_data/.walk-test.toml");
+        assert_eq!(
+            &paths[paths.len() - 3..],
+            &[
+                "/home/me/.config/walk-test/config.toml",
+                "/a/xdg/walk-test/config.toml",
+                "/etc/walk-test/config.toml"
+            ]
+        );
+        let found: Vec<&Candidate> = d.existing().collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].origin, Origin::Cwd);
+        assert!(d
+            .to_string()
+            .contains("test_data/.walk-test.toml (cwd): found"));
+
+        let env = fake_env(&[("WALK_TEST_CONFIG", "my.toml")]);
+        let d = discover_with("walk-test", "/", &env);
+        assert_eq!(
+            d.candidates[0],
+            Candidate {
+                path: PathBuf::from("my.toml"),
+                origin: Origin::EnvVar("WALK_TEST_CONFIG".to_string()),
+            }
+        );
+        let (tml, rep) = crate::tomlget::load_first_toml_report(
+            None,
+            discover_with("walk-test", "test_data/a", &env),
+        );
+        assert_eq!(rep.tried[0].path, "my.toml");
+        assert_eq!(
+            tml.unwrap().file(),
+            Some(Path::new("test_data/.walk-test.toml"))
+        );
+    }
+
+    #[test]
+    fn test_discover_odd_dir() {
+        let p = Path::new("~a/{b}/$c\\d");
+        assert_eq!(
+            expand_path(&escape_path(p).unwrap()).unwrap(),
+            "~a/{b}/$c\\d"
+        );
+
+        let dir = std::env::temp_dir().join("clap_conf_probe{x}$HOME");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".probeapp.toml"), "a = 1").unwrap();
+        let (tml, _) =
+            crate::tomlget::load_first_toml_report(None, discover_from("probeapp", &dir));
+        assert_eq!(
+            tml.unwrap().file(),
+            Some(dir.join(".probeapp.toml").as_path())
+        );
+        let all = crate::tomlget::load_all_toml(
+            None,
+            discover_from("probeapp", &dir),
+            crate::layered::ArrayMerge::Replace,
+        )
+        .unwrap();
+        assert_eq!(all.files(), &[dir.join(".probeapp.toml")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
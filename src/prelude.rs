@@ -1,4 +1,4 @@
-pub use crate::{with_toml_env, Filter, Getter};
+pub use crate::{with_config_env, with_json_env, with_toml_env, Filter, Getter};
 
 pub use crate::replace::ConfError;
 pub use clap::{clap_app, crate_version};
@@ -1,4 +1,8 @@
 //use failure_derive::*;
+use crate::{Filter, Getter};
+use std::cell::RefCell;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
 use thiserror::*;
 
 #[derive(Clone, Error, Debug)]
@@ -7,18 +11,90 @@ pub enum ConfError {
     Syntax,
     #[error("Environment variable not found")]
     VarNotFound,
-    #[error("Could not load file {}", 0)]
+    #[error("Could not load file {}", _0)]
     LoadError(String),
     #[error("{}", _0)]
     Mess(&'static str),
     #[error("{}", _0)]
     Message(String),
+    /// No source supplied the item. Holds every filter and key tried, in order.
+    #[error("Item not supplied, tried: {}", list_tried(.0))]
+    Missing(Vec<(Filter, String)>),
+    /// A value was found but could not be used.
+    #[error("Invalid value '{}' for {} '{}'{}: {}", .value, .filter, .key, show_in(.file), .message)]
+    Invalid {
+        key: String,
+        filter: Filter,
+        value: String,
+        file: Option<PathBuf>,
+        message: String,
+    },
+    /// A key refers to itself, directly or through other keys.
+    #[error("Reference cycle: {}", list_cycle(.0))]
+    Cycle(Vec<(Filter, String)>),
+    /// Several errors, as collected by a report::ConfReport
+    #[error("{}", crate::report::list_errors(.0))]
+    Many(Vec<ConfError>),
+    /// line and col start at 1
+    #[error("Parse error in {} at line {} column {}: {}", show_file(.file), .line, .col, .message)]
+    Parse {
+        file: Option<PathBuf>,
+        line: usize,
+        col: usize,
+        message: String,
+    },
+}
+
+fn list_tried(v: &[(Filter, String)]) -> String {
+    if v.is_empty() {
+        return "nothing".to_string();
+    }
+    let v: Vec<String> = v.iter().map(|(f, k)| format!("{} '{}'", f, k)).collect();
+    v.join(", ")
+}
+
+fn list_cycle(v: &[(Filter, String)]) -> String {
+    let v: Vec<String> = v.iter().map(|(f, k)| format!("{} '{}'", f, k)).collect();
+    v.join(" -> ")
+}
+
+fn show_in(p: &Option<PathBuf>) -> String {
+    match p {
+        Some(p) => format!(" in {}", p.display()),
+        None => String::new(),
+    }
+}
+
+fn show_file(p: &Option<PathBuf>) -> String {
+    match p {
+        Some(p) => p.display().to_string(),
+        None => "input".to_string(),
+    }
 }
 
 impl ConfError {
     pub fn with_info(self, s: &str) -> Self {
         ConfError::Message(format!("{} - {}", self, s))
     }
+
+    /// Set the file for a Parse error, or name it in a LoadError.
+    /// Other errors are unchanged.
+    pub fn in_file<P: AsRef<Path>>(self, p: P) -> Self {
+        match self {
+            ConfError::Parse {
+                line, col, message, ..
+            } => ConfError::Parse {
+                file: Some(p.as_ref().to_path_buf()),
+                line,
+                col,
+                message,
+            },
+            ConfError::LoadError(m) => {
+                ConfError::LoadError(format!("{}: {}", p.as_ref().display(), m))
+            }
+            e => e,
+        }
+    }
 }
 
 impl From<&'static str> for ConfError {
@@ -39,14 +115,50 @@ impl From<std::env::VarError> for ConfError {
     }
 }
 impl From<toml::de::Error> for ConfError {
-    fn from(_: toml::de::Error) -> Self {
-        ConfError::Syntax
+    fn from(e: toml::de::Error) -> Self {
+        match e.line_col() {
+            Some((line, col)) => ConfError::Parse {
+                file: None,
+                line: line + 1,
+                col: col + 1,
+                message: e.to_string(),
+            },
+            None => ConfError::Message(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_yaml::Error> for ConfError {
+    fn from(e: serde_yaml::Error) -> Self {
+        match e.location() {
+            Some(l) => ConfError::Parse {
+                file: None,
+                line: l.line(),
+                col: l.column(),
+                message: e.to_string(),
+            },
+            None => ConfError::Message(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ConfError {
+    fn from(e: serde_json::Error) -> Self {
+        match e.line() {
+            0 => ConfError::Message(e.to_string()),
+            line => ConfError::Parse {
+                file: None,
+                line,
+                col: e.column().max(1),
+                message: e.to_string(),
+            },
+        }
     }
 }
 
-type Job<E> = dyn Fn(&str) -> Result<String, E>;
+type Job<'a, E> = dyn Fn(&str) -> Result<String, E> + 'a;
 
-fn _replace<IT, E>(it: &mut IT, f: &Job<E>, depth: u8) -> Result<String, ConfError>
+fn _replace<IT, E>(it: &mut Peekable<IT>, f: &Job<'_, E>, depth: u8) -> Result<String, ConfError>
 where
     IT: Iterator<Item = char>,
     //J:Job<E>,
@@ -60,6 +172,30 @@ where
                 let s = _replace(it, f, depth + 1)?;
                 res.push_str(&f(&s)?);
             }
+            //"${VAR}" is the same as "{VAR}", "$$" is a literal '$'
+            '$' => match it.peek() {
+                Some('{') => {
+                    it.next();
+                    let s = _replace(it, f, depth + 1)?;
+                    res.push_str(&f(&s)?);
+                }
+                Some('$') => {
+                    it.next();
+                    res.push('$');
+                }
+                Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+                    let mut s = String::new();
+                    while let Some(c) = it.peek() {
+                        if !(c.is_ascii_alphanumeric() || *c == '_') {
+                            break;
+                        }
+                        s.push(*c);
+                        it.next();
+                    }
+                    res.push_str(&f(&s)?);
+                }
+                _ => res.push('$'),
+            },
             '}' => {
                 if depth == 0 {
                     return Err(ConfError::Syntax);
@@ -75,20 +211,76 @@ where
     Ok(res)
 }
 
-pub fn replace<E>(s: &str, f: &Job<E>) -> Result<String, ConfError>
+pub fn replace<E>(s: &str, f: &Job<'_, E>) -> Result<String, ConfError>
 where
     ConfError: From<E>,
     //J:Job<E>,
 {
-    _replace(&mut s.chars(), f, 0)
+    _replace(&mut s.chars().peekable(), f, 0)
 }
 
 pub fn replace_simple<F: 'static + Fn(&str) -> String>(s: &str, f: F) -> Result<String, ConfError> {
     replace::<ConfError>(s, &move |s| Ok(f(s)))
 }
 
+/// Look up an environment variable, shell style:
+/// "VAR", "VAR:-default" if unset or empty, and "VAR:?message" to fail with a message.
+pub fn env_var(v: &str) -> Result<String, ConfError> {
+    let set = |n: &str| std::env::var(n).ok().filter(|s| !s.is_empty());
+    if let Some(i) = v.find(":-") {
+        return Ok(set(&v[..i]).unwrap_or_else(|| v[i + 2..].to_string()));
+    }
+    if let Some(i) = v.find(":?") {
+        let (n, m) = (&v[..i], &v[i + 2..]);
+        return set(n).ok_or_else(|| match m {
+            "" => ConfError::Message(format!("{}: not set", n)),
+            m => ConfError::Message(format!("{}: {}", n, m)),
+        });
+    }
+    Ok(std::env::var(v)?)
+}
+
+/// Replace environment variables written as "{VAR}", "${VAR}" or "$VAR".
+/// The braced forms also take "${VAR:-default}" and "${VAR:?message}", see env_var.
 pub fn replace_env(s: &str) -> Result<String, ConfError> {
-    replace(s, &|v| std::env::var(v))
+    replace(s, &env_var)
+}
+
+fn _replace_keys<'a, G: Getter<'a>>(
+    g: &'a G,
+    s: &str,
+    stack: &RefCell<Vec<(Filter, String)>>,
+) -> Result<String, ConfError> {
+    let job = |v: &str| -> Result<String, ConfError> {
+        let (f, k) = match v.find(':').map(|i| (&v[..i], &v[i + 1..])) {
+            Some(("conf", k)) => (Filter::Conf, k),
+            Some(("arg", k)) => (Filter::Arg, k),
+            Some(("env", k)) => return env_var(k),
+            _ => return env_var(v),
+        };
+        let item = (f, k.to_string());
+        if stack.borrow().contains(&item) {
+            let mut c = stack.borrow().clone();
+            c.push(item);
+            return Err(ConfError::Cycle(c));
+        }
+        let r = g
+            .value(k, f)
+            .ok_or_else(|| ConfError::Missing(vec![item.clone()]))?
+            .to_string();
+        stack.borrow_mut().push(item);
+        let res = _replace_keys(g, &r, stack);
+        stack.borrow_mut().pop();
+        res
+    };
+    replace(s, &job)
+}
+
+/// Like replace_env, but "{conf:a.b}" and "{arg:name}" are looked up in the getter,
+/// and the values found are replaced in turn. "{env:VAR}" and "{VAR}" are environment variables.
+/// Values are used as displayed, so the getter should give plain strings.
+pub fn replace_keys<'a, G: Getter<'a>>(s: &str, g: &'a G) -> Result<String, ConfError> {
+    _replace_keys(g, s, &RefCell::new(Vec::new()))
 }
 
 #[cfg(test)]
@@ -99,7 +291,86 @@ mod test {
     }
     #[test]
     pub fn rep_test() {
-        let s2 = replace_simple("HELLO{WORLD}", &mini_rep).unwrap();
+        let s2 = replace_simple("HELLO{WORLD}", mini_rep).unwrap();
         assert_eq!(&s2, "HELLOworld");
     }
+
+    #[test]
+    pub fn shell_test() {
+        std::env::set_var("CLAP_CONF_TEST_REP", "abc");
+        std::env::remove_var("CLAP_CONF_TEST_UNSET");
+        let r = |s| replace_env(s).unwrap();
+        assert_eq!(r("x/{CLAP_CONF_TEST_REP}/y"), "x/abc/y");
+        assert_eq!(r("x/${CLAP_CONF_TEST_REP}/y"), "x/abc/y");
+        assert_eq!(r("x/$CLAP_CONF_TEST_REP/y"), "x/abc/y");
+        assert_eq!(r("${CLAP_CONF_TEST_UNSET:-def}"), "def");
+        assert_eq!(r("${CLAP_CONF_TEST_REP:-def}"), "abc");
+        assert_eq!(r("cost $$5, or $ 6"), "cost $5, or $ 6");
+        assert_eq!(
+            replace_env("${CLAP_CONF_TEST_UNSET:?needs setting}")
+                .unwrap_err()
+                .to_string(),
+            "CLAP_CONF_TEST_UNSET: needs setting"
+        );
+        assert!(replace_env("$CLAP_CONF_TEST_UNSET").is_err());
+        assert!(replace_env("${CLAP_CONF_TEST_REP").is_err());
+    }
+
+    #[test]
+    pub fn keys_test() {
+        std::env::set_var("CLAP_CONF_TEST_KEYS", "k");
+        let tml: toml::Value = "[paths]\nbase = \"/srv/{env:CLAP_CONF_TEST_KEYS}\"\n\
+             log = \"{conf:paths.base}/logs\"\na = \"${conf:paths.b}\"\nb = \"{conf:paths.a}\""
+            .parse()
+            .unwrap();
+        assert_eq!(
+            tml.grab().conf("paths.log").rep_keys().unwrap(),
+            "/srv/k/logs"
+        );
+        assert_eq!(
+            replace_keys("{conf:paths.a}", &tml)
+                .unwrap_err()
+                .to_string(),
+            "Reference cycle: conf 'paths.a' -> conf 'paths.b' -> conf 'paths.a'"
+        );
+        match replace_keys("{arg:profile}", &tml) {
+            Err(ConfError::Missing(v)) => assert_eq!(v, vec![(Filter::Arg, "profile".to_string())]),
+            r => panic!("expected Missing, got {:?}", r),
+        }
+    }
+
+    #[test]
+    pub fn error_text() {
+        let e = ConfError::Invalid {
+            key: "port".to_string(),
+            filter: Filter::Conf,
+            value: "80x".to_string(),
+            file: Some(PathBuf::from("a.toml")),
+            message: "invalid digit".to_string(),
+        };
+        assert_eq!(
+            e.to_string(),
+            "Invalid value '80x' for conf 'port' in a.toml: invalid digit"
+        );
+        let e = ConfError::LoadError("no such file".to_string());
+        assert_eq!(e.to_string(), "Could not load file no such file");
+        let e = ConfError::Missing(vec![
+            (Filter::Arg, "file".to_string()),
+            (Filter::Env, "FILE".to_string()),
+        ]);
+        assert_eq!(
+            e.to_string(),
+            "Item not supplied, tried: arg 'file', env 'FILE'"
+        );
+        let e = ConfError::from("a = ".parse::<toml::Value>().unwrap_err()).in_file("c.toml");
+        match e {
+            ConfError::Parse {
+                file, line, col, ..
+            } => {
+                assert_eq!(file, Some(PathBuf::from("c.toml")));
+                assert_eq!((line, col), (1, 5));
+            }
+            e => panic!("expected parse error, got {:?}", e),
+        }
+    }
 }
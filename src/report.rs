@@ -0,0 +1,145 @@
+//! Collect the errors from many grabs, so every missing or invalid option
+//! can be reported at once, rather than one per run.
+//!
+//! ```
+//! use clap_conf::prelude::*;
+//! use clap_conf::report::ConfReport;
+//!
+//! let matches = clap_app!(my_app=>
+//!     (@arg port: --port +takes_value "the port")
+//! ).get_matches_from(vec!["my_app"]);
+//! let cfg = with_toml_env(&matches, &["test_data/test1.toml"]);
+//!
+//! let mut rep = ConfReport::new();
+//! let port = rep.check(cfg.grab().arg("port").conf("server.port").env("MY_APP_PORT").t_req::<u16>());
+//! let file = rep.check(cfg.grab().conf("a.b.c").req());
+//! assert_eq!(port, None);
+//! assert_eq!(file, Some("hello".to_string()));
+//!
+//! let e = rep.finish().unwrap_err();
+//! assert!(e.to_string().contains("arg 'port'"));
+//! ```
+
+use crate::replace::ConfError;
+use crate::Filter;
+use std::fmt;
+
+/// Gathers the errors of many grabs.
+#[derive(Clone, Debug, Default)]
+pub struct ConfReport {
+    errors: Vec<ConfError>,
+}
+
+impl ConfReport {
+    pub fn new() -> Self {
+        ConfReport::default()
+    }
+
+    /// Keep the error if there is one, returning the value if there is not.
+    pub fn check<T>(&mut self, r: Result<T, ConfError>) -> Option<T> {
+        match r {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.add(e);
+                None
+            }
+        }
+    }
+
+    pub fn add(&mut self, e: ConfError) {
+        match e {
+            ConfError::Many(v) => self.errors.extend(v),
+            e => self.errors.push(e),
+        }
+    }
+
+    pub fn errors(&self) -> &[ConfError] {
+        &self.errors
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Ok if nothing failed, otherwise a single error holding every failure.
+    pub fn finish(self) -> Result<(), ConfError> {
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(ConfError::Many(self.errors)),
+        }
+    }
+}
+
+impl fmt::Display for ConfReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", list_errors(&self.errors))
+    }
+}
+
+/// How a user could supply a value for the key.
+/// Args are named as given to clap, as their flags may be spelled differently.
+fn suggest(f: Filter, k: &str) -> String {
+    match f {
+        Filter::Arg => format!("arg '{}'", k),
+        Filter::Conf => format!("'{} = ..' in the config file", k),
+        Filter::Env => format!("{}=.. in the environment", k),
+        Filter::Other(c) => format!("{} '{}'", Filter::Other(c), k),
+    }
+}
+
+/// One error, with suggestions of how to supply missing values.
+fn show_error(e: &ConfError) -> String {
+    match e {
+        ConfError::Missing(tried) if !tried.is_empty() => {
+            let v: Vec<String> = tried.iter().map(|(f, k)| suggest(*f, k)).collect();
+            format!("Missing '{}', set one of: {}", tried[0].1, v.join(", "))
+        }
+        e => e.to_string(),
+    }
+}
+
+pub(crate) fn list_errors(v: &[ConfError]) -> String {
+    let mut res = match v.len() {
+        1 => "1 configuration problem:".to_string(),
+        n => format!("{} configuration problems:", n),
+    };
+    for e in v {
+        res.push_str("\n  - ");
+        res.push_str(&show_error(e));
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Getter;
+    #[test]
+    fn test_report() {
+        let tml: toml::Value = "port = 0\nname = \"n\"".parse().unwrap();
+        let mut rep = ConfReport::new();
+        let name = rep.check(tml.grab().conf("name").req());
+        rep.check(tml.grab().conf("port").range(1..=65535).req());
+        rep.check(
+            tml.grab()
+                .arg("server_host")
+                .conf("server.host")
+                .env("MY_HOST")
+                .req(),
+        );
+        assert_eq!(name, Some("n".to_string()));
+        assert_eq!(rep.errors().len(), 2);
+        assert_eq!(
+            rep.to_string(),
+            "2 configuration problems:\n  \
+             - Invalid value '0' for conf 'port': not in range 1..=65535\n  \
+             - Missing 'server_host', set one of: arg 'server_host', 'server.host = ..' in the config file, \
+             MY_HOST=.. in the environment"
+        );
+        let e = rep.finish().unwrap_err();
+        let mut all = ConfReport::new();
+        all.add(e);
+        assert_eq!(all.errors().len(), 2);
+        assert!(ConfReport::new().finish().is_ok());
+    }
+}
@@ -0,0 +1,419 @@
+//! A table of options that can build the clap args and resolve every value,
+//! for those who would rather not use the derive macro.
+//!
+//! ```
+//! use clap_conf::prelude::*;
+//! use clap_conf::spec::{ConfSpec, OptSpec, ValType};
+//!
+//! let spec = ConfSpec::new()
+//!     .opt(OptSpec::new("filename").short("f").conf("a.b.c").env("MY_APP_FILE").help("input file"))
+//!     .opt(OptSpec::new("port").typ(ValType::Int).def("8080"))
+//!     .opt(OptSpec::new("verbose").typ(ValType::Bool));
+//!
+//! let matches = spec
+//!     .add_args(clap::App::new("my_app"))
+//!     .get_matches_from(vec!["my_app", "--verbose"]);
+//! let cfg = with_toml_env(&matches, &["test_data/test1.toml"]);
+//!
+//! let res = spec.resolve(&cfg).unwrap();
+//! assert_eq!(res.get("filename"), Some("hello"));
+//! assert_eq!(res.parse::<u16>("port").unwrap(), Some(8080));
+//! assert!(res.flag("verbose"));
+//!
+//! //list every option
+//! println!("{}", spec);
+//! ```
+
+use crate::replace::ConfError;
+use crate::value::parse_bool;
+use crate::{Filter, Getter};
+use clap::{App, Arg};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValType {
+    Str,
+    Int,
+    Float,
+    /// A flag on the command line, "true"/"false", "yes"/"no", "on"/"off" or "1"/"0" elsewhere
+    Bool,
+    /// Relative paths from a config file are local to that file (not for multi options)
+    Path,
+}
+
+impl fmt::Display for ValType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ValType::Str => "string",
+            ValType::Int => "int",
+            ValType::Float => "float",
+            ValType::Bool => "bool",
+            ValType::Path => "path",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl ValType {
+    fn check(&self, name: &str, v: &str) -> Result<(), ConfError> {
+        let ok = match self {
+            ValType::Int => v.parse::<i64>().is_ok(),
+            ValType::Float => v.parse::<f64>().is_ok(),
+            ValType::Bool => parse_bool(v).is_some(),
+            ValType::Str | ValType::Path => true,
+        };
+        match ok {
+            true => Ok(()),
+            false => Err(ConfError::Message(format!(
+                "Value '{}' for {} is not a valid {}",
+                v, name, self
+            ))),
+        }
+    }
+}
+
+/// A single option. The arg and conf names default to the option name,
+/// and the long flag to the arg name with '_' as '-'.
+#[derive(Clone, Debug)]
+pub struct OptSpec {
+    pub name: String,
+    pub arg: Option<String>,
+    pub short: Option<String>,
+    pub long: String,
+    pub conf: Option<String>,
+    pub env: Option<String>,
+    pub default: Option<String>,
+    pub help: Option<String>,
+    pub typ: ValType,
+    pub multi: bool,
+    pub required: bool,
+}
+
+impl OptSpec {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        let name = name.into();
+        OptSpec {
+            arg: Some(name.clone()),
+            conf: Some(name.clone()),
+            long: name.replace('_', "-"),
+            name,
+            short: None,
+            env: None,
+            default: None,
+            help: None,
+            typ: ValType::Str,
+            multi: false,
+            required: false,
+        }
+    }
+
+    /// Also sets the long flag to match, so call long after this to change it.
+    pub fn arg<S: Into<String>>(mut self, s: S) -> Self {
+        let s = s.into();
+        self.long = s.replace('_', "-");
+        self.arg = Some(s);
+        self
+    }
+
+    pub fn no_arg(mut self) -> Self {
+        self.arg = None;
+        self
+    }
+
+    pub fn short<S: Into<String>>(mut self, s: S) -> Self {
+        self.short = Some(s.into());
+        self
+    }
+
+    pub fn long<S: Into<String>>(mut self, s: S) -> Self {
+        self.long = s.into();
+        self
+    }
+
+    pub fn conf<S: Into<String>>(mut self, s: S) -> Self {
+        self.conf = Some(s.into());
+        self
+    }
+
+    pub fn no_conf(mut self) -> Self {
+        self.conf = None;
+        self
+    }
+
+    pub fn env<S: Into<String>>(mut self, s: S) -> Self {
+        self.env = Some(s.into());
+        self
+    }
+
+    pub fn def<S: Into<String>>(mut self, s: S) -> Self {
+        self.default = Some(s.into());
+        self
+    }
+
+    pub fn help<S: Into<String>>(mut self, s: S) -> Self {
+        self.help = Some(s.into());
+        self
+    }
+
+    pub fn typ(mut self, t: ValType) -> Self {
+        self.typ = t;
+        self
+    }
+
+    /// Accept multiple values
+    pub fn multi(mut self) -> Self {
+        self.multi = true;
+        self
+    }
+
+    /// Fail to resolve if no source or default supplies this
+    pub fn req(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    fn clap_arg(&self) -> Option<Arg<'_, '_>> {
+        let a = self.arg.as_ref()?;
+        let mut r = Arg::with_name(a).long(&self.long);
+        if let Some(s) = &self.short {
+            r = r.short(s);
+        }
+        if let Some(h) = &self.help {
+            r = r.help(h);
+        }
+        if self.typ != ValType::Bool {
+            r = r.takes_value(true).multiple(self.multi);
+        }
+        Some(r)
+    }
+
+    fn resolve<'a, G: Getter<'a>>(&self, g: &'a G) -> Result<Option<Vec<String>>, ConfError> {
+        let mut tried = Vec::new();
+        let found = [
+            (Filter::Arg, &self.arg),
+            (Filter::Conf, &self.conf),
+            (Filter::Env, &self.env),
+        ]
+        .iter()
+        .filter_map(|(f, k)| k.as_ref().map(|k| (*f, k)))
+        .find_map(|(f, k)| {
+            tried.push((f, k.clone()));
+            match (self.multi, self.typ) {
+                (true, _) => g.list(k, f),
+                //flags have no value on the command line
+                (false, ValType::Bool) if f == Filter::Arg => {
+                    g.bool_value(k, f).map(|b| vec![b.to_string()])
+                }
+                (false, ValType::Path) => {
+                    g.local_value(k, f).map(|p| vec![p.display().to_string()])
+                }
+                (false, _) => g.value(k, f).map(|v| vec![v.to_string()]),
+            }
+        });
+        let res = match (found, &self.default) {
+            (Some(v), _) => v,
+            (None, Some(d)) => match self.multi {
+                true => d.split(',').map(|s| s.trim().to_string()).collect(),
+                false => vec![d.clone()],
+            },
+            (None, None) if self.required => return Err(ConfError::Missing(tried)),
+            (None, None) => return Ok(None),
+        };
+        for v in &res {
+            self.typ.check(&self.name, v)?;
+        }
+        if self.typ == ValType::Bool {
+            //so Resolved::flag only has to look for "true"
+            let res = res
+                .iter()
+                .filter_map(|v| parse_bool(v))
+                .map(|b| b.to_string());
+            return Ok(Some(res.collect()));
+        }
+        Ok(Some(res))
+    }
+}
+
+/// A list of options
+#[derive(Clone, Debug, Default)]
+pub struct ConfSpec {
+    opts: Vec<OptSpec>,
+}
+
+impl ConfSpec {
+    pub fn new() -> Self {
+        ConfSpec::default()
+    }
+
+    pub fn opt(mut self, o: OptSpec) -> Self {
+        self.opts.push(o);
+        self
+    }
+
+    pub fn opts(&self) -> &[OptSpec] {
+        &self.opts
+    }
+
+    /// Add an arg to the clap App for every option that has one.
+    pub fn add_args<'a>(&'a self, app: App<'a, 'a>) -> App<'a, 'a> {
+        self.opts
+            .iter()
+            .filter_map(|o| o.clap_arg())
+            .fold(app, |app, a| app.arg(a))
+    }
+
+    /// Look up every option, checking values match their type.
+    pub fn resolve<'a, G: Getter<'a>>(&self, g: &'a G) -> Result<Resolved, ConfError> {
+        let mut map = BTreeMap::new();
+        for o in &self.opts {
+            if let Some(v) = o.resolve(g)? {
+                map.insert(o.name.clone(), v);
+            }
+        }
+        Ok(Resolved { map })
+    }
+}
+
+impl fmt::Display for ConfSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |s: &Option<String>| s.clone().unwrap_or_else(|| "-".to_string());
+        for o in &self.opts {
+            writeln!(
+                f,
+                "{} ({}): arg={} conf={} env={} default={}",
+                o.name,
+                o.typ,
+                show(&o.arg),
+                show(&o.conf),
+                show(&o.env),
+                show(&o.default)
+            )?;
+            if let Some(h) = &o.help {
+                writeln!(f, "    {}", h)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The values found for a ConfSpec by option name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Resolved {
+    map: BTreeMap<String, Vec<String>>,
+}
+
+impl Resolved {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.map.get(name)?.first().map(|s| s.as_str())
+    }
+
+    pub fn get_all(&self, name: &str) -> Option<&[String]> {
+        self.map.get(name).map(|v| v.as_slice())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.get(name) == Some("true")
+    }
+
+    pub fn path(&self, name: &str) -> Option<PathBuf> {
+        self.get(name).map(PathBuf::from)
+    }
+
+    pub fn parse<T: FromStr>(&self, name: &str) -> Result<Option<T>, ConfError>
+    where
+        T::Err: fmt::Display,
+    {
+        match self.get(name) {
+            Some(v) => v.parse().map(Some).map_err(|e| {
+                ConfError::Message(format!("Could not parse '{}' for {}: {}", v, name, e))
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_spec() {
+        let spec = ConfSpec::new()
+            .opt(OptSpec::new("num").short("n").typ(ValType::Int).conf("a.n"))
+            .opt(OptSpec::new("items").multi().conf("a.items"))
+            .opt(OptSpec::new("word").no_arg().conf("a.w").def("x"))
+            .opt(OptSpec::new("flag").typ(ValType::Bool))
+            .opt(OptSpec::new("path").env("CLAP_CONF_NOT_SET"));
+        let m = spec
+            .add_args(App::new("test_app"))
+            .get_matches_from("test_app -n 3".split(' '));
+        let tml: toml::Value = "[a]\nitems=[\"c\",\"d\"]".parse().unwrap();
+        let h = crate::clap_env(&m).hold(tml);
+        let r = spec.resolve(&h).unwrap();
+        assert_eq!(r.parse::<i32>("num").unwrap(), Some(3));
+        assert_eq!(
+            r.get_all("items"),
+            Some(&["c".to_string(), "d".to_string()][..])
+        );
+        assert_eq!(r.get("word"), Some("x"));
+        assert!(!r.flag("flag"));
+        assert_eq!(r.get("path"), None);
+
+        let must = spec.clone().opt(OptSpec::new("must").req());
+        assert!(must.resolve(&h).is_err());
+
+        let m = spec
+            .add_args(App::new("test_app"))
+            .get_matches_from("test_app -n 3x".split(' '));
+        assert!(spec.resolve(&crate::clap_env(&m)).is_err());
+    }
+
+    #[test]
+    fn test_spec_sources() {
+        let spec = ConfSpec::new()
+            .opt(
+                OptSpec::new("list")
+                    .multi()
+                    .no_conf()
+                    .env("CLAP_CONF_TEST_SPEC_LIST")
+                    .def("a,b"),
+            )
+            .opt(OptSpec::new("on").typ(ValType::Bool).conf("a.on"))
+            .opt(OptSpec::new("off").typ(ValType::Bool).conf("a.off"))
+            .opt(OptSpec::new("loud").typ(ValType::Bool).conf("a.loud"))
+            .opt(OptSpec::new("dir").typ(ValType::Path).conf("a.dir"));
+        let m = spec
+            .add_args(App::new("test_app"))
+            .get_matches_from(vec!["test_app"]);
+        let tml: toml::Value = "[a]\non = \"yes\"\noff = 0\ndir = \"d\"".parse().unwrap();
+        let h = crate::clap_env(&m).hold(crate::convert::Localizer::new(tml.clone(), "/cfg"));
+        let r = spec.resolve(&h).unwrap();
+        assert_eq!(
+            r.get_all("list"),
+            Some(&["a".to_string(), "b".to_string()][..])
+        );
+        assert!(r.flag("on"));
+        assert!(!r.flag("off"));
+        assert_eq!(r.get("off"), Some("false"));
+        assert_eq!(r.path("dir"), Some(PathBuf::from("/cfg/d")));
+
+        let m = spec
+            .add_args(App::new("test_app"))
+            .get_matches_from(vec!["test_app", "--loud"]);
+        let r = spec.resolve(&crate::clap_env(&m).hold(tml)).unwrap();
+        assert!(r.flag("loud"));
+
+        //long flags spell '_' as '-', as the derive macro does
+        let spec = ConfSpec::new()
+            .opt(OptSpec::new("max_size"))
+            .opt(OptSpec::new("min_size").long("least"));
+        let m = spec
+            .add_args(App::new("test_app"))
+            .get_matches_from("test_app --max-size 5 --least 1".split(' '));
+        let r = spec.resolve(&crate::clap_env(&m)).unwrap();
+        assert_eq!(r.get("max_size"), Some("5"));
+        assert_eq!(r.get("min_size"), Some("1"));
+    }
+}
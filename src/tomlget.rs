@@ -1,39 +1,84 @@
 use crate::convert::Localizer;
-use crate::replace::{replace_env, ConfError};
+use crate::layered::{ArrayMerge, Layered};
+use crate::load::{first_or_err, load_first, LoadFail, LoadReport};
+use crate::paths::expand_path;
+use crate::replace::ConfError;
+use crate::value::parse_bool;
 use crate::{Filter, Getter};
-use std::path::{Path, PathBuf};
 use toml::Value;
 
 pub fn load_toml<S: AsRef<str>>(s: S) -> Result<Localizer<Value>, ConfError> {
-    let fname = replace_env(s.as_ref())?;
+    let fname = expand_path(s.as_ref())?;
     let fcont = std::fs::read_to_string(&fname)?;
-    let v = fcont.parse::<Value>()?;
-    let fpar = PathBuf::from(PathBuf::from(fname).parent().unwrap_or(Path::new("./")));
+    let v = fcont
+        .parse::<Value>()
+        .map_err(|e| ConfError::from(e).in_file(&fname))?;
 
-    Ok(Localizer::new(v, fpar))
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// As load_toml, but keeps the reason for failing, including the position of parse errors.
+pub fn try_load_toml<S: AsRef<str>>(s: S) -> Result<Localizer<Value>, LoadFail> {
+    let fname = expand_path(s.as_ref())?;
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v = fcont.parse::<Value>().map_err(|e| {
+        let (line, col) = e.line_col().unwrap_or((0, 0));
+        LoadFail::Parse {
+            line: line + 1,
+            col: col + 1,
+            message: e.to_string(),
+        }
+    })?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// Load the first toml file that can be loaded, along with a report of every candidate tried.
+pub fn load_first_toml_report<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> (Option<Localizer<Value>>, LoadReport) {
+    load_first(a, i, |s| try_load_toml(s))
 }
 
 pub fn load_first_toml<S: AsRef<str>, IT: IntoIterator<Item = S>>(
     a: Option<&str>,
     i: IT,
 ) -> Result<Localizer<Value>, ConfError> {
-    if let Some(m) = a {
-        println!("config selected = {} ", m);
-        match load_toml(m) {
-            Ok(v) => return Ok(v),
-            Err(e) => {
-                println!("Could not load selected file {:?}", e);
-                return Err(e);
+    first_or_err(a, load_first_toml_report(a, i))
+}
+
+/// Load every candidate that exists and merge them, earlier files taking precedence.
+/// Missing files are skipped, but a file that exists and fails to load is an error.
+/// If "a" is supplied (normally from a "config" arg), only that file is loaded.
+pub fn load_all_toml<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+    arrays: ArrayMerge,
+) -> Result<Layered, ConfError> {
+    let cands: Vec<String> = match a {
+        Some(a) => vec![a.to_string()],
+        None => i.into_iter().map(|s| s.as_ref().to_string()).collect(),
+    };
+    let mut loaded = Vec::new();
+    for c in cands {
+        match try_load_toml(&c) {
+            Ok(l) => {
+                log::info!("config loaded from {}", c);
+                loaded.push(l)
+            }
+            Err(LoadFail::NotFound) if a.is_none() => {
+                log::debug!("config candidate {} not found", c)
             }
+            Err(e) => return Err(ConfError::from(e).in_file(&c)),
         }
     }
-    for s in i {
-        match load_toml(s) {
-            Ok(m) => return Ok(m),
-            Err(_) => continue,
-        }
+    let mut res = Layered::new(arrays);
+    for l in loaded.into_iter().rev() {
+        let file = l.file().map(|p| p.to_path_buf()).unwrap_or_default();
+        res.add_over(l.into_inner(), file);
     }
-    Err("could not load".into())
+    Ok(res)
 }
 
 fn dig<S: AsRef<str>, I: Iterator<Item = S>>(v: &Value, mut i: I) -> Option<&Value> {
@@ -54,6 +99,10 @@ impl<'a> Getter<'a> for Value {
         (&self).bool_flag(s, f)
     }
 
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        (&self).has(s, f)
+    }
+
     fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
         match (&self).value(s, f)? {
             Value::String(s) => Some(s.clone()),
@@ -86,6 +135,15 @@ impl<'a> Getter<'a> for &'a Value {
         }
     }
 
+    fn bool_value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<bool> {
+        match self.value(s, f)? {
+            Value::Boolean(b) => Some(*b),
+            Value::String(s) => parse_bool(s),
+            Value::Integer(i) => parse_bool(&i.to_string()),
+            _ => None,
+        }
+    }
+
     fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<&'a Value> {
         if f != Filter::Conf {
             return None;
@@ -102,7 +160,7 @@ impl<'a> Getter<'a> for &'a Value {
         let v = dig(self, s.as_ref().split("."))?;
 
         if let Value::Array(a) = v {
-            return Some(a.into_iter());
+            return Some(a.iter());
         }
         None
     }
@@ -124,6 +182,37 @@ mod tomltests {
         assert_eq!(r.as_str().unwrap(), "green");
     }
 
+    #[test]
+    fn test_report() {
+        let (v, r) = load_first_toml_report(
+            None,
+            [
+                "test_data/none.toml",
+                "test_data/bad.toml",
+                "test_data/test1.toml",
+            ],
+        );
+        assert!(v.is_some());
+        assert_eq!(r.tried.len(), 3);
+        assert_eq!(r.tried[0].fail, Some(LoadFail::NotFound));
+        match &r.tried[1].fail {
+            Some(LoadFail::Parse { line, col, .. }) => assert_eq!((*line, *col), (3, 3)),
+            e => panic!("expected parse error, got {:?}", e),
+        }
+        assert_eq!(r.loaded(), Some("test_data/test1.toml"));
+
+        let (v, r) = load_first_toml_report(Some("test_data/bad.toml"), ["test_data/test1.toml"]);
+        assert!(v.is_none());
+        assert_eq!(r.tried.len(), 1);
+        assert!(load_first_toml(Some("test_data/bad.toml"), ["test_data/test1.toml"]).is_err());
+
+        let e = load_first_toml(Some("/nope/app.toml"), ["test_data/test1.toml"]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Could not load file /nope/app.toml: not found"
+        );
+    }
+
     #[test]
     fn test_iter() {
         let t: Value = "[a.b]\ncar=[\"red\",\"green\"]".parse().unwrap();
@@ -0,0 +1,188 @@
+//! Parsers for human friendly durations, byte sizes and percentages,
+//! such as "1h30m", "512MiB" and "75%".
+//!
+//! Plain numbers are accepted too, as seconds, bytes and percent respectively.
+
+use crate::value::ConfValue;
+use std::time::Duration;
+
+/// Split "1.5h30m" into [("1.5","h"),("30","m")]
+fn split_units(s: &str) -> Result<Vec<(f64, String)>, String> {
+    let mut res = Vec::new();
+    let mut it = s.trim().chars().peekable();
+    while it.peek().is_some() {
+        let mut n = String::new();
+        while let Some(c) = it.peek() {
+            if !(c.is_ascii_digit() || *c == '.') {
+                break;
+            }
+            n.push(*c);
+            it.next();
+        }
+        while let Some(' ') = it.peek() {
+            it.next();
+        }
+        let mut u = String::new();
+        while let Some(c) = it.peek() {
+            if !c.is_alphabetic() {
+                break;
+            }
+            u.push(*c);
+            it.next();
+        }
+        while let Some(' ') = it.peek() {
+            it.next();
+        }
+        let n = n
+            .parse::<f64>()
+            .map_err(|_| format!("expected a number in '{}'", s))?;
+        res.push((n, u));
+    }
+    if res.is_empty() {
+        return Err("empty value".to_string());
+    }
+    Ok(res)
+}
+
+/// Parse durations like "30s", "250ms", "1h30m" or "2d".
+/// A number with no unit is seconds.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let mut secs = 0.0;
+    for (n, u) in split_units(s)? {
+        let mul = match u.as_str() {
+            "" | "s" | "sec" | "secs" => 1.0,
+            "ns" => 1e-9,
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "m" | "min" | "mins" => 60.0,
+            "h" | "hr" | "hrs" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            "w" | "week" | "weeks" => 604_800.0,
+            u => return Err(format!("unknown duration unit '{}'", u)),
+        };
+        secs += n * mul;
+    }
+    secs_duration(secs)
+}
+
+/// A number of seconds as a Duration, failing if negative, not finite or too large.
+pub fn secs_duration(secs: f64) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(secs)
+        .map_err(|_| format!("duration of {} seconds is out of range", secs))
+}
+
+/// Parse byte sizes like "512MiB", "10k" or "1.5GB".
+/// SI units (k, MB) are powers of 1000, binary units (Ki, MiB) powers of 1024.
+/// A number with no unit is bytes.
+pub fn parse_bytes(s: &str) -> Result<u64, String> {
+    let mut res = 0.0;
+    for (n, u) in split_units(s)? {
+        let lu = u.to_lowercase();
+        let lu = lu.strip_suffix('b').unwrap_or(&lu);
+        let mul: f64 = match lu {
+            "" => 1.0,
+            "k" => 1e3,
+            "m" => 1e6,
+            "g" => 1e9,
+            "t" => 1e12,
+            "p" => 1e15,
+            "ki" => 1024.0,
+            "mi" => 1024f64.powi(2),
+            "gi" => 1024f64.powi(3),
+            "ti" => 1024f64.powi(4),
+            "pi" => 1024f64.powi(5),
+            _ => return Err(format!("unknown size unit '{}'", u)),
+        };
+        res += n * mul;
+    }
+    num_bytes(res)
+}
+
+/// A number of bytes, failing if negative, not finite or too large for a u64.
+pub fn num_bytes(n: f64) -> Result<u64, String> {
+    let n = n.round();
+    //u64::MAX as f64 rounds up to 2^64, so anything at or above it is too large
+    match n.is_finite() && n >= 0. && n < u64::MAX as f64 {
+        true => Ok(n as u64),
+        false => Err(format!("size of {} bytes is out of range", n)),
+    }
+}
+
+/// Parse percentages like "75%" or "12.5". The result is in percent, so "75%" gives 75.0.
+pub fn parse_percent(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let s = s.strip_suffix('%').unwrap_or(s).trim_end();
+    let n = s
+        .parse()
+        .map_err(|_| format!("expected a percentage in '{}'", s))?;
+    num_percent(n)
+}
+
+/// A percentage, failing if not finite.
+pub fn num_percent(n: f64) -> Result<f64, String> {
+    match n.is_finite() {
+        true => Ok(n),
+        false => Err(format!("percentage {} is not a number", n)),
+    }
+}
+
+/// Run a unit parser on a value, allowing plain numbers from typed config files.
+pub(crate) fn on_value<T, N, P>(v: &ConfValue, num: N, parse: P) -> Result<T, String>
+where
+    N: Fn(f64) -> Result<T, String>,
+    P: Fn(&str) -> Result<T, String>,
+{
+    match v {
+        ConfValue::Int(i) => num(*i as f64),
+        ConfValue::Float(f) => num(*f),
+        ConfValue::Str(s) => parse(s),
+        v => Err(format!("expected a string or number, found {}", v)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_duration() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1h 30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1.5h"), Ok(Duration::from_secs(5400)));
+        assert!(parse_duration("30 parsecs").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("99999999999999999999").is_err());
+        let num = |f: f64| on_value(&ConfValue::Float(f), secs_duration, parse_duration);
+        assert!(num(1e100).is_err());
+        assert!(num(f64::INFINITY).is_err());
+        assert_eq!(num(1.5), Ok(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(parse_bytes("512MiB"), Ok(512 * 1024 * 1024));
+        assert_eq!(parse_bytes("10k"), Ok(10_000));
+        assert_eq!(parse_bytes("1.5GB"), Ok(1_500_000_000));
+        assert_eq!(parse_bytes("42"), Ok(42));
+        assert_eq!(parse_bytes("2 KiB"), Ok(2048));
+        assert!(parse_bytes("12 furlongs").is_err());
+        assert!(parse_bytes("99999999999999PiB").is_err());
+        let num = |v: ConfValue| on_value(&v, num_bytes, parse_bytes);
+        assert!(num(ConfValue::Int(-5)).is_err());
+        assert!(num(ConfValue::Float(1e30)).is_err());
+        assert!(num(ConfValue::Float(f64::NAN)).is_err());
+        assert_eq!(num(ConfValue::Int(7)), Ok(7));
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(parse_percent("75%"), Ok(75.0));
+        assert_eq!(parse_percent(" 12.5 % "), Ok(12.5));
+        assert_eq!(parse_percent("20"), Ok(20.0));
+        assert!(parse_percent("lots").is_err());
+        assert!(parse_percent("nan%").is_err());
+        assert!(parse_percent("inf%").is_err());
+        assert!(on_value(&ConfValue::Float(f64::INFINITY), num_percent, parse_percent).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+//! A config value that keeps its native type, rather than being turned into a String.
+//!
+//! ConfValue is itself a Getter, so a file can be converted to a ConfValue tree once,
+//! and typed values read straight out of it.
+//! Strings from args and the environment are only parsed when a typed value is asked for.
+//!
+//! ```
+//! use clap_conf::prelude::*;
+//! use clap_conf::value::ConfValue;
+//!
+//! let tml: toml::Value = "port = 8080\nratio = 1e100".parse().unwrap();
+//! let cv = ConfValue::from(tml);
+//!
+//! assert_eq!(cv.grab().conf("port").int(), Some(8080));
+//! assert_eq!(cv.grab().conf("ratio").float(), Some(1e100));
+//! ```
+
+use crate::convert::Wrapper;
+use crate::env::Enver;
+use crate::{Filter, Getter};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The environment, giving ConfValue strings.
+pub type ValueEnver = Wrapper<Enver, fn(String) -> ConfValue>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Datetime(toml::value::Datetime),
+    List(Vec<ConfValue>),
+    Table(BTreeMap<String, ConfValue>),
+}
+
+/// Parse true/false, yes/no, on/off and 1/0, ignoring case.
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+impl ConfValue {
+    /// Ints, and strings that parse as ints.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            ConfValue::Int(i) => Some(*i),
+            ConfValue::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Floats, ints, and strings that parse as floats.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            ConfValue::Float(f) => Some(*f),
+            ConfValue::Int(i) => Some(*i as f64),
+            ConfValue::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Bools, and strings that parse as bools, see parse_bool.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ConfValue::Bool(b) => Some(*b),
+            ConfValue::Str(s) => parse_bool(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_datetime(&self) -> Option<toml::value::Datetime> {
+        match self {
+            ConfValue::Datetime(d) => Some(d.clone()),
+            ConfValue::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ConfValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn dig<S: AsRef<str>, I: Iterator<Item = S>>(&self, mut i: I) -> Option<&ConfValue> {
+        match i.next() {
+            None => Some(self),
+            Some(s) => match self {
+                ConfValue::Table(t) => t.get(s.as_ref())?.dig(i),
+                ConfValue::List(l) => l.get(s.as_ref().parse::<usize>().ok()?)?.dig(i),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ConfValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfValue::Str(s) => write!(f, "{}", s),
+            ConfValue::Int(i) => write!(f, "{}", i),
+            ConfValue::Float(v) => write!(f, "{}", v),
+            ConfValue::Bool(b) => write!(f, "{}", b),
+            ConfValue::Datetime(d) => write!(f, "{}", d),
+            ConfValue::List(l) => {
+                let v: Vec<String> = l.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", v.join(", "))
+            }
+            ConfValue::Table(t) => {
+                let v: Vec<String> = t.iter().map(|(k, v)| format!("{} = {}", k, v)).collect();
+                write!(f, "{{{}}}", v.join(", "))
+            }
+        }
+    }
+}
+
+impl From<String> for ConfValue {
+    fn from(s: String) -> Self {
+        ConfValue::Str(s)
+    }
+}
+
+impl From<&str> for ConfValue {
+    fn from(s: &str) -> Self {
+        ConfValue::Str(s.to_string())
+    }
+}
+
+impl From<toml::Value> for ConfValue {
+    fn from(v: toml::Value) -> Self {
+        use toml::Value;
+        match v {
+            Value::String(s) => ConfValue::Str(s),
+            Value::Integer(i) => ConfValue::Int(i),
+            Value::Float(f) => ConfValue::Float(f),
+            Value::Boolean(b) => ConfValue::Bool(b),
+            Value::Datetime(d) => ConfValue::Datetime(d),
+            Value::Array(a) => ConfValue::List(a.into_iter().map(ConfValue::from).collect()),
+            Value::Table(t) => {
+                ConfValue::Table(t.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+        }
+    }
+}
+
+impl From<serde_json::Value> for ConfValue {
+    fn from(v: serde_json::Value) -> Self {
+        use serde_json::Value;
+        match v {
+            Value::Null => ConfValue::Table(BTreeMap::new()),
+            Value::String(s) => ConfValue::Str(s),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => ConfValue::Int(i),
+                None => ConfValue::Float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::Bool(b) => ConfValue::Bool(b),
+            Value::Array(a) => ConfValue::List(a.into_iter().map(ConfValue::from).collect()),
+            Value::Object(o) => {
+                ConfValue::Table(o.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+        }
+    }
+}
+
+impl From<serde_yaml::Value> for ConfValue {
+    fn from(v: serde_yaml::Value) -> Self {
+        use serde_yaml::Value;
+        match v {
+            Value::Null => ConfValue::Table(BTreeMap::new()),
+            Value::String(s) => ConfValue::Str(s),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => ConfValue::Int(i),
+                None => ConfValue::Float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::Bool(b) => ConfValue::Bool(b),
+            Value::Sequence(a) => ConfValue::List(a.into_iter().map(ConfValue::from).collect()),
+            Value::Mapping(m) => ConfValue::Table(
+                m.into_iter()
+                    .filter_map(|(k, v)| {
+                        let k = match k {
+                            Value::String(s) => s,
+                            Value::Number(n) => n.to_string(),
+                            Value::Bool(b) => b.to_string(),
+                            _ => return None,
+                        };
+                        Some((k, v.into()))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> Getter<'a> for ConfValue {
+    type Out = ConfValue;
+    type Iter = std::vec::IntoIter<ConfValue>;
+    fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        match self.value(s, f) {
+            Some(v) => v.as_bool().unwrap_or(false),
+            None => false,
+        }
+    }
+
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        f == Filter::Conf && self.dig(s.as_ref().split('.')).is_some()
+    }
+
+    /// Only single values, like the other getters. Use values for lists.
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<ConfValue> {
+        if f != Filter::Conf {
+            return None;
+        }
+        match self.dig(s.as_ref().split('.'))? {
+            ConfValue::List(_) | ConfValue::Table(_) => None,
+            v => Some(v.clone()),
+        }
+    }
+
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<Self::Iter> {
+        if f != Filter::Conf {
+            return None;
+        }
+        match self.dig(s.as_ref().split('.'))? {
+            ConfValue::List(l) => Some(l.clone().into_iter()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::convert::Localizer;
+    use clap::clap_app;
+
+    #[test]
+    fn test_native() {
+        let tml: toml::Value =
+            "[a]\nport = 80\nf = 1e100\non = true\nwhen = 1979-05-27T07:32:00Z\nl = [1, 2]"
+                .parse()
+                .unwrap();
+        let cv = ConfValue::from(tml);
+        assert_eq!(cv.value("a.port", Filter::Conf), Some(ConfValue::Int(80)));
+        assert_eq!(cv.grab().conf("a.f").float(), Some(1e100));
+        assert_eq!(cv.grab().conf("a.on").boolean(), Some(true));
+        assert_eq!(
+            cv.grab().conf("a.when").datetime().map(|d| d.to_string()),
+            Some("1979-05-27T07:32:00Z".to_string())
+        );
+        let l: Vec<i64> = cv
+            .grab_multi()
+            .conf("a.l")
+            .done()
+            .unwrap()
+            .filter_map(|v| v.as_int())
+            .collect();
+        assert_eq!(l, vec![1, 2]);
+        assert!(cv.has("a", Filter::Conf));
+        assert!(!cv.has("a.nope", Filter::Conf));
+        assert_eq!(cv.value("a", Filter::Conf), None);
+        assert_eq!(cv.grab().conf("a.l").done(), None);
+    }
+
+    #[test]
+    fn test_args_parsed() {
+        let m = clap_app!(test_app=>
+            (@arg port: --port +takes_value "port")
+        )
+        .get_matches_from("test_app --port 9000".split(' '));
+        let tml: toml::Value = "port = 80\nname = \"n\"".parse().unwrap();
+        let h = crate::env::Enver {}
+            .wrap(ConfValue::from)
+            .hold(&m)
+            .hold(Localizer::new(ConfValue::from(tml), ""));
+        let h2 = crate::with_toml_env_typed(&m, ["test_data/test1.toml"]);
+        assert_eq!(
+            h2.grab().conf("a.b.c").done(),
+            Some(ConfValue::from("hello"))
+        );
+        assert_eq!(h2.grab().arg("port").int(), Some(9000));
+        assert_eq!(h.grab().arg("port").conf("port").int(), Some(9000));
+        assert_eq!(h.grab().arg("x").conf("port").int(), Some(80));
+        assert_eq!(h.grab().conf("name").int(), None);
+
+        let js: serde_json::Value = serde_json::from_str(r#"{"a":[1, 2.5, "x"]}"#).unwrap();
+        let l: Vec<ConfValue> = ConfValue::from(js)
+            .values("a", Filter::Conf)
+            .unwrap()
+            .collect();
+        assert_eq!(
+            l,
+            vec![
+                ConfValue::Int(1),
+                ConfValue::Float(2.5),
+                ConfValue::Str("x".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::convert::Localizer;
+use crate::load::{first_or_err, load_first, LoadFail, LoadReport};
+use crate::paths::expand_path;
+use crate::replace::ConfError;
+use crate::{Filter, Getter};
+use serde_yaml::Value;
+
+pub fn load_yaml<S: AsRef<str>>(s: S) -> Result<Localizer<Value>, ConfError> {
+    let fname = expand_path(s.as_ref())?;
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v =
+        serde_yaml::from_str::<Value>(&fcont).map_err(|e| ConfError::from(e).in_file(&fname))?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// As load_yaml, but keeps the reason for failing, including the position of parse errors.
+pub fn try_load_yaml<S: AsRef<str>>(s: S) -> Result<Localizer<Value>, LoadFail> {
+    let fname = expand_path(s.as_ref())?;
+    let fcont = std::fs::read_to_string(&fname)?;
+    let v = serde_yaml::from_str::<Value>(&fcont).map_err(ConfError::from)?;
+
+    Ok(Localizer::from_file(v, fname))
+}
+
+/// Load the first yaml file that can be loaded, along with a report of every candidate tried.
+pub fn load_first_yaml_report<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> (Option<Localizer<Value>>, LoadReport) {
+    load_first(a, i, |s| try_load_yaml(s))
+}
+
+pub fn load_first_yaml<S: AsRef<str>, IT: IntoIterator<Item = S>>(
+    a: Option<&str>,
+    i: IT,
+) -> Result<Localizer<Value>, ConfError> {
+    first_or_err(a, load_first_yaml_report(a, i))
+}
+
+fn dig<S: AsRef<str>, I: Iterator<Item = S>>(v: &Value, mut i: I) -> Option<&Value> {
+    match i.next() {
+        None => Some(v),
+        Some(s) => match v {
+            Value::Mapping(m) => dig(m.get(&Value::String(s.as_ref().to_string()))?, i),
+            Value::Sequence(a) => dig(a.get(s.as_ref().parse::<usize>().ok()?)?, i),
+            _ => None,
+        },
+    }
+}
+
+impl<'a> Getter<'a> for Value {
+    type Out = String;
+    type Iter = std::vec::IntoIter<String>;
+    fn bool_flag<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        if f != Filter::Conf {
+            return false;
+        }
+        match dig(self, s.as_ref().split('.')) {
+            Some(v) => v.as_bool().unwrap_or(false),
+            None => false,
+        }
+    }
+
+    fn has<S: AsRef<str>>(&self, s: S, f: Filter) -> bool {
+        f == Filter::Conf && dig(self, s.as_ref().split('.')).is_some()
+    }
+
+    fn value<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<String> {
+        if f != Filter::Conf {
+            return None;
+        }
+        match dig(self, s.as_ref().split('.'))? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    fn values<S: AsRef<str>>(&self, s: S, f: Filter) -> Option<std::vec::IntoIter<String>> {
+        if f != Filter::Conf {
+            return None;
+        }
+        match dig(self, s.as_ref().split('.'))? {
+            Value::Sequence(a) => {
+                let res: Vec<String> = a
+                    .iter()
+                    .filter_map(|v| v.as_str().map(|vr| vr.to_string()))
+                    .collect();
+                Some(res.into_iter())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod yamltests {
+    use super::*;
+    use std::path::PathBuf;
+    #[test]
+    fn test_load() {
+        let t: Value = serde_yaml::from_str("a:\n  b:\n    c:\n      car: red").unwrap();
+        let r = t.value("a.b.c.car", Filter::Conf).unwrap();
+        assert_eq!(r, "red");
+
+        let t: Value = serde_yaml::from_str("a:\n  b:\n    - car: red\n    - car: green").unwrap();
+        let r = t.value("a.b.1.car", Filter::Conf).unwrap();
+        assert_eq!(r, "green");
+        assert_eq!(t.value("a.b.0.car", Filter::Conf), Some("red".to_string()));
+    }
+
+    #[test]
+    fn test_iter() {
+        let t: Value = serde_yaml::from_str("a:\n  b:\n    car: [red, green]").unwrap();
+        let mut r = t
+            .values("a.b.car", Filter::Conf)
+            .expect("Could not get values");
+        assert_eq!(r.next().unwrap(), "red");
+        assert_eq!(r.next().unwrap(), "green");
+    }
+
+    #[test]
+    fn test_local() {
+        let l = load_yaml("test_data/test1.yaml").unwrap();
+        assert_eq!(l.grab().conf("a.b.c").done(), Some("hello".to_string()));
+        assert_eq!(
+            l.grab_local().conf("a.b.d").done(),
+            Some(PathBuf::from("test_data/world"))
+        );
+    }
+}